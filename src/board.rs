@@ -1,103 +1,232 @@
 use crate::dictionary_generator::Dictionary;
+use crate::gaddag::{Anagram, Gaddag, Hooks, SEPARATOR};
+use crate::language::Language;
+use std::cmp::Ordering;
 use std::collections::{HashSet, HashMap};
+use std::error::Error;
+use std::fmt;
 use std::fs;
 
+const RACK_SIZE: usize = 7;
+
 pub struct Board<'a> {
     pub letters: &'a String,
     pub dictionary: &'a Dictionary,
     scorer: LetterScorer,
-    parsed_board: ParsedBoard
+    parsed_board: ParsedBoard,
+    ranking: Ranking
 }
 
 impl Board<'_>  {
     pub fn new<'a>(
         letters: &'a String,
         dictionary: &'a Dictionary,
-        letterpoints_path: &'a String,
         layout_path: &'a String,
-        current_board_path: &'a String) -> Board<'a> {
-
-        Board {
-            letters: letters,
-            dictionary: dictionary,
-            scorer: LetterScorer::parse(letterpoints_path),
-            parsed_board: ParsedBoard::parse(layout_path, current_board_path)
-        }
+        current_board_path: &'a String,
+        rules: &Rules,
+        ranking: Ranking) -> Result<Board<'a>, BoardError> {
+
+        Ok(
+            Board {
+                letters,
+                dictionary,
+                scorer: LetterScorer::new(dictionary.language(), rules),
+                parsed_board: ParsedBoard::parse(layout_path, current_board_path)?,
+                ranking
+            }
+        )
     }
 
-    pub fn anagrams(&self) -> Vec<String> {
-        let combos = self.combinations();
-        let mut anagrams = self.dictionary.get_anagrams_for(&combos);
+    pub fn anagrams(&self) -> Vec<Anagram> {
+        let mut anagrams: Vec<Anagram> = self.dictionary
+            .anagrams(self.letters)
+            .into_iter()
+            .filter(|anagram| self.ranking.allows(anagram.commonness))
+            .collect();
 
         anagrams.sort_by(|a, b| {
-            let a_score = self.scorer.score(b, self.letters);
-            let b_score = self.scorer.score(a, self.letters);
-            a_score.cmp(&b_score)
+            let a_score = self.scorer.score(&b.word, self.letters);
+            let b_score = self.scorer.score(&a.word, self.letters);
+            self.ranking
+                .compare(a.commonness, b.commonness)
+                .then(a_score.cmp(&b_score))
         });
 
         anagrams
     }
 
-    fn optimal_plays(&self) -> Vec<Play> {
-        let mut plays = vec![];
+    pub fn optimal_plays(&self) -> Vec<Play> {
+        let gaddag = self.dictionary.gaddag();
+        let size = self.parsed_board.tiles.len();
+        let opening = self.parsed_board.is_opening_turn();
+        let mut found = HashSet::new();
 
-        if self.parsed_board.is_opening_turn() {
-            let (endx, y) = self.parsed_board.origin();
-            let anagrams = self.anagrams();
-            let word = &anagrams[0]; // This can _and_ should be optimized
-            let sx = endx - word.len() + 1;
+        for direction in [Direction::Horizontal, Direction::Vertical] {
+            let cross_checks = self.parsed_board.cross_checks(direction, self.dictionary);
 
-            for tx in sx..=endx {
-                plays.push(
-                    Play {
-                        points: self.scorer.score(word, self.letters),
-                        position: (tx, y),
-                        word: word.to_string()
+            for line in 0..size {
+                for anchor in 0..size {
+                    let position = ParsedBoard::coordinates(direction, line, anchor);
+                    if !self.parsed_board.is_anchor(position, opening) {
+                        continue
                     }
-                );
+
+                    let mut generator = Generator {
+                        board: &self.parsed_board,
+                        gaddag,
+                        cross_checks: &cross_checks,
+                        direction,
+                        line,
+                        anchor,
+                        found: &mut found
+                    };
+
+                    generator.extend(anchor, "", self.letters, gaddag.root(), true);
+                }
             }
-        } else {
-            // Start by detecting where each letter is in the tiles
-            // then start connecting them with your existing letters.
-            // See which letter(s) forms the largest amount of points.
         }
 
+        let mut plays: Vec<Play> =
+            found
+                .into_iter()
+                .filter_map(|(position, direction, word)| {
+                    self.play_at(&word, position, direction).ok()
+                })
+                .filter(|play| self.ranking.allows(play.commonness))
+                .collect();
+
+        plays.sort_by(|a, b| {
+            self.ranking
+                .compare(a.commonness, b.commonness)
+                .then_with(|| b.points.cmp(&a.points))
+                .then_with(|| a.word.cmp(&b.word))
+                .then_with(|| a.position.cmp(&b.position))
+                .then_with(|| a.direction.cmp(&b.direction))
+        });
         plays
     }
 
-    fn combinations(&self) -> HashSet<String> {
-        let mut combinations = HashSet::new();
-        let mut output = String::new();
+    // The words on the board that can be extended with a single letter, only
+    // counting the hooks that have a free tile to go on.
+    pub fn hookable_words(&self) -> Vec<HookableWord> {
+        let mut hookable = vec![];
+
+        for (word, position, direction) in self.parsed_board.words() {
+            let length = word.chars().count();
+            let (line, start) = match direction {
+                Direction::Horizontal => (position.1, position.0),
+                Direction::Vertical => (position.0, position.1)
+            };
+            let is_free = |offset: Option<usize>| {
+                offset
+                    .map(|offset| ParsedBoard::coordinates(direction, line, offset))
+                    .is_some_and(|(x, y)| {
+                        self.parsed_board.tile_at(x, y).is_some() &&
+                            self.parsed_board.letter_at(x, y).is_none()
+                    })
+            };
+
+            let mut hooks = self.dictionary.hooks(&word);
+            if !is_free(start.checked_sub(1)) {
+                hooks.front.clear();
+            }
+            if !is_free(Some(start + length)) {
+                hooks.back.clear();
+            }
 
-        for i in 2..=self.letters.len() {
-            self.find_unique_combinations(0, i, &mut combinations, &mut output);
+            if !hooks.is_empty() {
+                hookable.push(HookableWord { position, direction, hooks });
+            }
         }
 
-        combinations
+        hookable
     }
 
-    // Took this algorithm from:
-    // https://www.techiedelight.com/find-distinct-combinations-of-given-length/
-    fn find_unique_combinations(&self,
-                                offset: usize,
-                                length: usize,
-                                hash: &mut HashSet<String>,
-                                list: &mut String) {
+    // Puts the word on the board at the given position and scores it,
+    // including the cross words it forms. When there's a choice where the
+    // blanks go, the one with the most points is taken. It's an error when
+    // the word doesn't fit on the board, can't be made with the rack, isn't
+    // connected to the letters on the board, or forms a word that isn't in
+    // the dictionary.
+    pub fn play_at(&self,
+                   word: &str,
+                   position: (usize, usize),
+                   direction: Direction) -> Result<Play, PlacementError> {
+
+        let slot = self.parsed_board.slot(position, direction, word)?;
+        let placements = slot.placements(word, self.letters)?;
+        self.parsed_board.connects(&slot)?;
+
+        if !self.dictionary.contains(word) {
+            return Err(PlacementError::UnknownWord(word.to_string()));
+        }
 
-        if self.letters.len() == 0 || length > self.letters.len() {
-            return;
+        let mut best: Option<Play> = None;
+
+        for placed_tiles in placements {
+            let play = self.score_play(word, position, direction, placed_tiles)?;
+
+            if best.as_ref().is_none_or(|best| play.points > best.points) {
+                best = Some(play);
+            }
         }
 
-        if length == 0 {
-            hash.insert(list.clone());
-            return;
+        Ok(best.unwrap())
+    }
+
+    fn score_play(&self,
+                  word: &str,
+                  position: (usize, usize),
+                  direction: Direction,
+                  placed_tiles: Vec<PlacedTile>) -> Result<Play, PlacementError> {
+
+        // Every cross word is scored on its own, only counting the tile that
+        // was placed from the rack.
+        let mut cross_words = vec![];
+
+        for tile in &placed_tiles {
+            if let Some((cross_word, position)) = self.parsed_board.cross_word(direction, tile) {
+                if !self.dictionary.contains(&cross_word) {
+                    return Err(PlacementError::UnknownWord(cross_word));
+                }
+
+                let points = self.scorer.score_with_board(
+                    &cross_word,
+                    std::slice::from_ref(tile),
+                    &self.parsed_board,
+                    direction.perpendicular(),
+                    position
+                )?;
+
+                cross_words.push(CrossWord { word: cross_word, position, points });
+            }
         }
 
-        for i in offset..self.letters.len() {
-            list.push(self.letters.chars().nth(i).unwrap());
-            self.find_unique_combinations(i + 1, length - 1, hash, list);
-            list.remove(list.len() - 1);
+        let points = self.scorer.score_with_board(
+            word,
+            &placed_tiles,
+            &self.parsed_board,
+            direction,
+            position
+        )? + cross_words.iter().map(|cross_word| cross_word.points).sum::<u16>();
+
+        let mut leave = self.letters.to_string();
+        for tile in &placed_tiles {
+            leave = leave.replacen(tile.rack_letter(), "", 1);
         }
+
+        Ok(
+            Play {
+                word: word.to_string(),
+                points,
+                position,
+                direction,
+                placed_tiles,
+                cross_words,
+                leave,
+                commonness: self.dictionary.commonness(word)
+            }
+        )
     }
 }
 
@@ -108,6 +237,8 @@ struct ParsedBoard {
 #[derive(Debug, Eq, PartialEq)]
 enum Tile {
     Letter(char),
+    // A blank that was played earlier, holding the letter it stands for
+    Blank(char),
     Empty,
     Start,
     DoubleLetter,
@@ -117,30 +248,34 @@ enum Tile {
 }
 
 impl ParsedBoard {
-    fn parse(layout_path: &String, current_board_path: &String) -> ParsedBoard {
-        let current_board = fs::read_to_string(current_board_path).unwrap();
+    fn parse(layout_path: &String, current_board_path: &String) -> Result<ParsedBoard, BoardError> {
         let layout = fs::read_to_string(layout_path).unwrap();
+        let current_board = fs::read_to_string(current_board_path).unwrap();
+        ParsedBoard::from_contents(&layout, &current_board)
+    }
 
-        let mut tiles: Vec<Vec<Tile>> =
-            layout
-                .split_terminator("\n")
-                .map(|line| {
-                    line
-                        .chars()
-                        .map(|tile| {
-                            match tile {
-                                '.' => Tile::Empty,
-                                '1' => Tile::Start,
-                                '2' => Tile::DoubleLetter,
-                                '3' => Tile::TripleLetter,
-                                '4' => Tile::DoubleWord,
-                                '5' => Tile::TripleWord,
-                                _ => panic!("Invalid tile")
-                            }
-                        })
-                        .collect()
-                })
-                .collect();
+    fn from_contents(layout: &str, current_board: &str) -> Result<ParsedBoard, BoardError> {
+        let mut tiles: Vec<Vec<Tile>> = vec![];
+
+        for (y, l) in layout.split_terminator("\n").enumerate() {
+            let mut row = vec![];
+
+            for (x, tile) in l.chars().enumerate() {
+                row.push(
+                    match tile {
+                        '.' => Tile::Empty,
+                        '1' => Tile::Start,
+                        '2' => Tile::DoubleLetter,
+                        '3' => Tile::TripleLetter,
+                        '4' => Tile::DoubleWord,
+                        '5' => Tile::TripleWord,
+                        _ => return Err(BoardError::InvalidTile { line: y + 1, column: x + 1, tile })
+                    }
+                );
+            }
+
+            tiles.push(row);
+        }
 
         for (y, l) in current_board.split_terminator("\n").enumerate() {
             for (x, c) in l.chars().enumerate() {
@@ -148,11 +283,22 @@ impl ParsedBoard {
                     continue
                 }
 
-                tiles[y][x] = Tile::Letter(c);
+                let tile = tiles
+                    .get_mut(y)
+                    .and_then(|row| row.get_mut(x))
+                    .ok_or(BoardError::OffTheBoard { line: y + 1, column: x + 1, letter: c })?;
+
+                // Blanks on the board are written down in lowercase
+                let uppercase: Vec<char> = c.to_uppercase().collect();
+                *tile = match uppercase[..] {
+                    [letter] if c.is_lowercase() => Tile::Blank(letter),
+                    [letter] if letter == c && c.is_alphabetic() => Tile::Letter(c),
+                    _ => return Err(BoardError::InvalidLetter { line: y + 1, column: x + 1, letter: c })
+                };
             }
         }
 
-        ParsedBoard { tiles: tiles }
+        Ok(ParsedBoard { tiles })
     }
 
     fn origin(&self) -> (usize, usize) {
@@ -175,7 +321,7 @@ impl ParsedBoard {
     fn is_opening_turn(&self) -> bool {
         for row in self.tiles.iter() {
             for tile in row {
-                if let Tile::Letter(_) = tile {
+                if let Tile::Letter(_) | Tile::Blank(_) = tile {
                     return false;
                 }
             }
@@ -184,273 +330,1387 @@ impl ParsedBoard {
         true
     }
 
-    fn play_points(&self) -> Vec<Vec<(usize, usize)>> {
-        let mut coords = vec![];
-        let mut start_coords = vec![];
+    fn letter_at(&self, x: usize, y: usize) -> Option<char> {
+        match self.tile_at(x, y) {
+            Some(Tile::Letter(c)) | Some(Tile::Blank(c)) => Some(*c),
+            _ => None
+        }
+    }
+
+    // Returns the coordinates of the n-th tile in a line, where a line is
+    // either a row (horizontal) or a column (vertical).
+    fn coordinates(direction: Direction, line: usize, offset: usize) -> (usize, usize) {
+        match direction {
+            Direction::Horizontal => (offset, line),
+            Direction::Vertical => (line, offset)
+        }
+    }
+
+    // A tile "touches" the existing letters on the board when one of the
+    // tiles perpendicular to the direction of the word holds a letter.
+    fn touches_letter(&self, direction: Direction, (x, y): (usize, usize)) -> bool {
+        let (before, after) = match direction {
+            Direction::Horizontal => (y.checked_sub(1).map(|ty| (x, ty)), (x, y + 1)),
+            Direction::Vertical => (x.checked_sub(1).map(|tx| (tx, y)), (x + 1, y))
+        };
+
+        before.and_then(|(bx, by)| self.letter_at(bx, by)).is_some() ||
+            self.letter_at(after.0, after.1).is_some()
+    }
+
+    // Returns the word that is formed perpendicular to `direction` when the
+    // tile is placed, together with the position of its first letter. When
+    // the tile doesn't touch any letters in that direction, there's no word.
+    fn cross_word(&self, direction: Direction, tile: &PlacedTile) -> Option<(String, (usize, usize))> {
+        let cross_direction = direction.perpendicular();
+        let (x, y) = tile.position;
+        let (line, offset) = match cross_direction {
+            Direction::Horizontal => (y, x),
+            Direction::Vertical => (x, y)
+        };
+
+        let mut start = offset;
+        while start > 0 {
+            let (tx, ty) = Self::coordinates(cross_direction, line, start - 1);
+            if self.letter_at(tx, ty).is_none() {
+                break
+            }
+            start -= 1;
+        }
+
+        let mut word = String::new();
+        let mut current = start;
+
+        loop {
+            let (tx, ty) = Self::coordinates(cross_direction, line, current);
+            match self.letter_at(tx, ty) {
+                Some(c) => word.push(c),
+                None if current == offset => word.push(tile.letter),
+                None => break
+            }
+            current += 1;
+        }
+
+        if word.chars().count() < 2 {
+            return None;
+        }
+
+        Some((word, Self::coordinates(cross_direction, line, start)))
+    }
+
+    // Every word of two or more letters on the board, with the position of
+    // its first letter.
+    fn words(&self) -> Vec<(String, (usize, usize), Direction)> {
+        let size = self.tiles.len();
+        let mut words = vec![];
+
+        for direction in [Direction::Horizontal, Direction::Vertical] {
+            for line in 0..size {
+                let mut word = String::new();
+
+                for offset in 0..=size {
+                    let (x, y) = Self::coordinates(direction, line, offset);
+                    if let Some(c) = self.letter_at(x, y) {
+                        word.push(c);
+                        continue
+                    }
+
+                    let length = word.chars().count();
+                    if length >= 2 {
+                        let position = Self::coordinates(direction, line, offset - length);
+                        words.push((word.clone(), position, direction));
+                    }
+                    word.clear();
+                }
+            }
+        }
+
+        words
+    }
+
+    // An anchor is an empty tile next to a letter on the board, every new
+    // word has to cover one. On the opening turn, that's the start tile.
+    fn is_anchor(&self, (x, y): (usize, usize), opening: bool) -> bool {
+        if self.letter_at(x, y).is_some() {
+            return false;
+        }
+
+        if opening {
+            return (x, y) == self.origin();
+        }
+
+        self.touches_letter(Direction::Horizontal, (x, y)) ||
+            self.touches_letter(Direction::Vertical, (x, y))
+    }
+
+    // For every empty tile that touches a letter perpendicular to the
+    // direction, collects the letters that form a valid cross word there.
+    // Tiles that aren't in here accept any letter.
+    fn cross_checks(&self, direction: Direction, dictionary: &Dictionary) -> HashMap<(usize, usize), Vec<char>> {
+        let mut cross_checks = HashMap::new();
+
         for (y, row) in self.tiles.iter().enumerate() {
-            for (x, tile) in row.iter().enumerate() {
-                if let Tile::Letter(_) = tile {
-                    start_coords.push((x, y));
+            for x in 0..row.len() {
+                if self.letter_at(x, y).is_some() || !self.touches_letter(direction, (x, y)) {
+                    continue
                 }
+
+                let allowed = dictionary
+                    .alphabet()
+                    .iter()
+                    .filter(|c| {
+                        let tile = PlacedTile { position: (x, y), letter: **c, blank: false };
+
+                        match self.cross_word(direction, &tile) {
+                            Some((cross_word, _)) => dictionary.contains(&cross_word),
+                            None => true
+                        }
+                    })
+                    .cloned()
+                    .collect();
+
+                cross_checks.insert((x, y), allowed);
             }
         }
 
-        for i in 1..7 {
-            let mut sub_coords = vec![];
-            for (x, y) in &start_coords {
-                let mut ranges: Vec<(usize, usize)> = vec![];
-                //let r = *x..(x + i);
-                //let r2 = (x - i)..*x;
-                //let r3 = *y..(y + i);
-                //let r4 = (y - i)..*y;
+        cross_checks
+    }
+
+    // A word has to go through the start tile on the opening turn, and touch
+    // a letter that's on the board after that
+    fn connects(&self, slot: &Slot) -> Result<(), PlacementError> {
+        let opening = self.is_opening_turn();
+        let connected = (0..slot.pattern.len())
+            .filter(|i| slot.pattern[*i].is_none())
+            .any(|i| self.is_anchor(slot.coordinates(i), opening));
+
+        match (connected, opening) {
+            (true, _) => Ok(()),
+            (false, true) => Err(PlacementError::MissesStart),
+            (false, false) => Err(PlacementError::NotConnected)
+        }
+    }
+
+    fn tile_at(&self, x: usize, y: usize) -> Option<&Tile> {
+        self.tiles.get(y).and_then(|row| row.get(x))
+    }
+
+    // The slot the word would take up on the board, as long as it fits and
+    // there's no letter right before or after it that would make it longer
+    fn slot(&self,
+            position: (usize, usize),
+            direction: Direction,
+            word: &str) -> Result<Slot, PlacementError> {
+
+        let mut slot = Slot { position, direction, pattern: vec![] };
 
-                //println!("{:?} {:?} {:?} {:?}", r, r2, r3, r4);
+        for i in 0..word.chars().count() {
+            let (x, y) = slot.coordinates(i);
+
+            if self.tile_at(x, y).is_none() {
+                return Err(PlacementError::OutOfBounds { position: (x, y) });
             }
 
-            coords.push(sub_coords);
+            slot.pattern.push(self.letter_at(x, y));
         }
 
-        coords
+        let (x, y) = position;
+        let before = match direction {
+            Direction::Horizontal => x.checked_sub(1).map(|tx| (tx, y)),
+            Direction::Vertical => y.checked_sub(1).map(|ty| (x, ty))
+        };
+        let after = slot.coordinates(slot.pattern.len());
+
+        for (x, y) in before.into_iter().chain([after]) {
+            if self.letter_at(x, y).is_some() {
+                return Err(PlacementError::OccupiedEnd { position: (x, y) });
+            }
+        }
+
+        Ok(slot)
     }
 }
 
+// Walks the GADDAG from a single anchor: first to the left of the anchor,
+// then after the separator to the right, filling the empty tiles with the
+// letters on the rack that pass the cross checks.
+struct Generator<'a> {
+    board: &'a ParsedBoard,
+    gaddag: &'a Gaddag,
+    cross_checks: &'a HashMap<(usize, usize), Vec<char>>,
+    direction: Direction,
+    line: usize,
+    anchor: usize,
+    found: &'a mut HashSet<((usize, usize), Direction, String)>
+}
+
+impl Generator<'_> {
+    fn letter_at(&self, offset: usize) -> Option<char> {
+        let (x, y) = ParsedBoard::coordinates(self.direction, self.line, offset);
+        self.board.letter_at(x, y)
+    }
+
+    fn is_free(&self, offset: Option<usize>) -> bool {
+        match offset {
+            Some(offset) => self.letter_at(offset).is_none(),
+            None => true
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.board.tiles.len()
+    }
+
+    fn extend(&mut self, offset: usize, word: &str, rack: &str, node: usize, left: bool) {
+        if let Some(letter) = self.letter_at(offset) {
+            if let Some(next) = self.gaddag.next(node, letter) {
+                self.go_on(offset, letter, word, rack, next, left);
+            }
+            return;
+        }
+
+        let position = ParsedBoard::coordinates(self.direction, self.line, offset);
+        let allowed = |c: &char| {
+            match self.cross_checks.get(&position) {
+                Some(letters) => letters.contains(c),
+                None => true
+            }
+        };
+
+        let mut tried = HashSet::new();
+
+        for c in rack.chars() {
+            if c == '?' || !tried.insert(c) || !allowed(&c) {
+                continue
+            }
+
+            if let Some(next) = self.gaddag.next(node, c) {
+                self.go_on(offset, c, word, &rack.replacen(c, "", 1), next, left);
+            }
+        }
+
+        if rack.contains('?') {
+            for c in self.gaddag.letters(node) {
+                if !allowed(&c) {
+                    continue
+                }
+
+                let next = self.gaddag.next(node, c).unwrap();
+                self.go_on(offset, c, word, &rack.replacen('?', "", 1), next, left);
+            }
+        }
+    }
+
+    fn go_on(&mut self, offset: usize, letter: char, word: &str, rack: &str, node: usize, left: bool) {
+        let size = self.size();
+
+        if left {
+            let word = format!("{}{}", letter, word);
+            let left_free = self.is_free(offset.checked_sub(1));
+            let right_free = self.is_free(Some(self.anchor + 1).filter(|o| *o < size));
+
+            if self.gaddag.is_terminal(node) && left_free && right_free {
+                self.record(offset, word.clone());
+            }
+
+            if offset > 0 {
+                self.extend(offset - 1, &word, rack, node, true);
+            }
+
+            if let Some(next) = self.gaddag.next(node, SEPARATOR) {
+                if left_free && self.anchor + 1 < size {
+                    self.extend(self.anchor + 1, &word, rack, next, false);
+                }
+            }
+        } else {
+            let word = format!("{}{}", word, letter);
+            let right_free = self.is_free(Some(offset + 1).filter(|o| *o < size));
+
+            if self.gaddag.is_terminal(node) && right_free {
+                self.record(offset + 1 - word.chars().count(), word.clone());
+            }
+
+            if offset + 1 < size {
+                self.extend(offset + 1, &word, rack, node, false);
+            }
+        }
+    }
+
+    fn record(&mut self, start: usize, word: String) {
+        let position = ParsedBoard::coordinates(self.direction, self.line, start);
+        self.found.insert((position, self.direction, word));
+    }
+}
+
+// A range of tiles in a single row or column where a word could be placed.
+// The pattern contains the letters that are already on the board, and `None`
+// for every tile that still has to be filled from the rack.
+#[derive(Debug, Eq, PartialEq)]
+struct Slot {
+    position: (usize, usize),
+    direction: Direction,
+    pattern: Vec<Option<char>>
+}
+
+impl Slot {
+    fn coordinates(&self, offset: usize) -> (usize, usize) {
+        let (x, y) = self.position;
+
+        match self.direction {
+            Direction::Horizontal => (x + offset, y),
+            Direction::Vertical => (x, y + offset)
+        }
+    }
+
+    // Tries to fill the slot with the given word using the letters from the
+    // rack. Returns every way the tiles can be placed: when a blank stands in
+    // for a letter that's on the rack as well, it can go on any of the tiles
+    // with that letter, which changes the score when they're on different
+    // bonus tiles.
+    fn placements(&self, word: &str, letters: &str) -> Result<Vec<Vec<PlacedTile>>, PlacementError> {
+        let mut rack = letters.to_string();
+        let mut new_tiles = vec![];
+
+        for (i, (c, tile)) in word.chars().zip(&self.pattern).enumerate() {
+            match tile {
+                Some(letter) => {
+                    if *letter != c {
+                        return Err(
+                            PlacementError::ConflictingLetter {
+                                position: self.coordinates(i),
+                                letter: c,
+                                existing: *letter
+                            }
+                        );
+                    }
+                },
+                None => {
+                    if rack.contains(c) {
+                        rack = rack.replacen(c, "", 1);
+                    } else if rack.contains('?') {
+                        rack = rack.replacen('?', "", 1);
+                    } else {
+                        return Err(PlacementError::NotOnRack(c));
+                    }
+
+                    new_tiles.push((self.coordinates(i), c));
+                }
+            }
+        }
+
+        if new_tiles.is_empty() {
+            return Err(PlacementError::NoNewTiles);
+        }
+
+        let mut placements = vec![];
+        Slot::fill(&new_tiles, letters, &mut vec![], &mut placements);
+        Ok(placements)
+    }
+
+    // Places the remaining tiles in every possible way. A blank is only used
+    // for a letter when there are fewer of them on the rack than the word
+    // still needs.
+    fn fill(new_tiles: &[((usize, usize), char)],
+            rack: &str,
+            placed_tiles: &mut Vec<PlacedTile>,
+            placements: &mut Vec<Vec<PlacedTile>>) {
+
+        let Some((&(position, letter), rest)) = new_tiles.split_first() else {
+            placements.push(placed_tiles.clone());
+            return
+        };
+
+        let needed = new_tiles.iter().filter(|(_, c)| *c == letter).count();
+        let on_rack = rack.matches(letter).count();
+
+        for blank in [false, true] {
+            let rack_letter = if blank { '?' } else { letter };
+            let possible = if blank { on_rack < needed } else { on_rack > 0 };
+
+            if possible && rack.contains(rack_letter) {
+                placed_tiles.push(PlacedTile { position, letter, blank });
+                Slot::fill(rest, &rack.replacen(rack_letter, "", 1), placed_tiles, placements);
+                placed_tiles.pop();
+            }
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum PlacementError {
+    OutOfBounds { position: (usize, usize) },
+    ConflictingLetter { position: (usize, usize), letter: char, existing: char },
+    NotOnRack(char),
+    NoNewTiles,
+    UnknownLetter(char),
+    OccupiedEnd { position: (usize, usize) },
+    NotConnected,
+    MissesStart,
+    UnknownWord(String)
+}
+
+impl fmt::Display for PlacementError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlacementError::OutOfBounds { position } => {
+                write!(f, "The word runs off the board at {:?}", position)
+            },
+            PlacementError::ConflictingLetter { position, letter, existing } => {
+                write!(f, "Can't put a {} on the {} at {:?}", letter, existing, position)
+            },
+            PlacementError::NotOnRack(c) => {
+                write!(f, "The letter {} isn't on the rack", c)
+            },
+            PlacementError::NoNewTiles => {
+                write!(f, "The word doesn't use any letters from the rack")
+            },
+            PlacementError::UnknownLetter(c) => {
+                write!(f, "There are no points for the letter {}", c)
+            },
+            PlacementError::OccupiedEnd { position } => {
+                write!(f, "The word runs into the letter at {:?}", position)
+            },
+            PlacementError::NotConnected => {
+                write!(f, "The word doesn't touch any of the letters on the board")
+            },
+            PlacementError::MissesStart => {
+                write!(f, "The first word has to cover the start tile")
+            },
+            PlacementError::UnknownWord(word) => {
+                write!(f, "{} isn't in the dictionary", word)
+            }
+        }
+    }
+}
+
+impl Error for PlacementError {}
+
+// A mistake in the layout or in the current board, with the line and column
+// where it is in the file
+#[derive(Debug, Eq, PartialEq)]
+pub enum BoardError {
+    InvalidTile { line: usize, column: usize, tile: char },
+    OffTheBoard { line: usize, column: usize, letter: char },
+    InvalidLetter { line: usize, column: usize, letter: char }
+}
+
+impl fmt::Display for BoardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BoardError::InvalidTile { line, column, tile } => {
+                write!(f, "line {}, column {}: '{}' isn't a tile of the layout", line, column, tile)
+            },
+            BoardError::OffTheBoard { line, column, letter } => {
+                write!(f, "line {}, column {}: the letter '{}' is off the board", line, column, letter)
+            },
+            BoardError::InvalidLetter { line, column, letter } => {
+                write!(f, "line {}, column {}: '{}' isn't a letter", line, column, letter)
+            }
+        }
+    }
+}
+
+impl Error for BoardError {}
+
+#[derive(Debug, Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Direction {
+    Horizontal,
+    Vertical
+}
+
+impl Direction {
+    fn perpendicular(&self) -> Direction {
+        match self {
+            Direction::Horizontal => Direction::Vertical,
+            Direction::Vertical => Direction::Horizontal
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Direction::Horizontal => write!(f, "horizontal"),
+            Direction::Vertical => write!(f, "vertical")
+        }
+    }
+}
+
+// A tile that is placed on the board from the rack. When it's a blank, the
+// letter is the one the blank stands for.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PlacedTile {
+    pub position: (usize, usize),
+    pub letter: char,
+    pub blank: bool
+}
+
+impl PlacedTile {
+    fn rack_letter(&self) -> char {
+        if self.blank {
+            '?'
+        } else {
+            self.letter
+        }
+    }
+}
+
+// A word that is formed perpendicular to the direction of a play
+#[derive(Debug, Eq, PartialEq)]
+pub struct CrossWord {
+    pub word: String,
+    pub position: (usize, usize),
+    pub points: u16
+}
+
+// A single way to put letters from the rack on the board. The points include
+// the points of all the cross words, and the leave is what's left of the rack
+// after the play.
 #[derive(Debug, Eq, PartialEq)]
-struct Play {
-    word: String,
-    points: u16,
-    position: (usize, usize)
+pub struct Play {
+    pub word: String,
+    pub points: u16,
+    pub position: (usize, usize),
+    pub direction: Direction,
+    pub placed_tiles: Vec<PlacedTile>,
+    pub cross_words: Vec<CrossWord>,
+    pub leave: String,
+    pub commonness: Option<u8>
+}
+
+impl Play {
+    pub fn blanks(&self) -> Vec<&PlacedTile> {
+        self.placed_tiles.iter().filter(|tile| tile.blank).collect()
+    }
 }
 
+impl fmt::Display for Play {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at {:?} {} for {} points",
+            self.word,
+            self.position,
+            self.direction,
+            self.points
+        )?;
+
+        for tile in self.blanks() {
+            write!(f, ", blank as {}", tile.letter)?;
+        }
+
+        for cross_word in &self.cross_words {
+            write!(f, ", {} for {} points", cross_word.word, cross_word.points)?;
+        }
+
+        write!(f, ", leaves \"{}\"", self.leave)?;
+
+        if let Some(commonness) = self.commonness {
+            write!(f, ", commonness {}", commonness)?;
+        }
+
+        Ok(())
+    }
+}
+
+// A word on the board with the hooks that fit on the board
+#[derive(Debug)]
+pub struct HookableWord {
+    pub position: (usize, usize),
+    pub direction: Direction,
+    pub hooks: Hooks
+}
+
+impl fmt::Display for HookableWord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {:?} {}: {}", self.hooks.word, self.position, self.direction, self.hooks)
+    }
+}
+
+// How the suggestions are ranked. By default the highest scoring ones come
+// first, but the words that are used more often can be put first instead, and
+// the rare words can be left out. It only makes a difference when the
+// dictionary has a frequency list.
+#[derive(Debug, Default)]
+pub struct Ranking {
+    pub by_commonness: bool,
+    pub min_commonness: u8
+}
+
+impl Ranking {
+    fn allows(&self, commonness: Option<u8>) -> bool {
+        commonness.is_none_or(|commonness| commonness >= self.min_commonness)
+    }
+
+    // The more common word comes first, when they're sorted by commonness.
+    fn compare(&self, a: Option<u8>, b: Option<u8>) -> Ordering {
+        if self.by_commonness {
+            b.cmp(&a)
+        } else {
+            Ordering::Equal
+        }
+    }
+}
+
+// The rules that differ between the different ways Wordfeud can be played.
+// They're read from a `rules.txt` file in the language folder, and fall back
+// to the defaults of the regular game.
+#[derive(Debug, Eq, PartialEq)]
+pub struct Rules {
+    pub bingo_bonus: u16
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules { bingo_bonus: 40 }
+    }
+}
+
+impl Rules {
+    pub fn parse(path: &String) -> Result<Rules, RulesError> {
+        let contents = fs::read_to_string(path).unwrap();
+        Rules::from_contents(&contents)
+    }
+
+    // Empty lines and lines starting with a `#` are skipped, like in the
+    // language manifest
+    fn from_contents(contents: &str) -> Result<Rules, RulesError> {
+        let mut rules = Rules::default();
+
+        for (i, line) in contents.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+
+            let (key, value) = line.split_once(',').ok_or_else(|| RulesError::InvalidLine {
+                line: line_number,
+                content: line.to_string()
+            })?;
+
+            match key.trim() {
+                "bingo_bonus" => {
+                    rules.bingo_bonus = value.trim().parse::<u16>().map_err(|_| {
+                        RulesError::InvalidNumber { line: line_number, value: value.to_string() }
+                    })?
+                },
+                key => {
+                    return Err(RulesError::UnknownRule { line: line_number, key: key.to_string() })
+                }
+            }
+        }
+
+        Ok(rules)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum RulesError {
+    InvalidLine { line: usize, content: String },
+    UnknownRule { line: usize, key: String },
+    InvalidNumber { line: usize, value: String }
+}
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RulesError::InvalidLine { line, content } => {
+                write!(f, "line {}: '{}' isn't a valid rule", line, content)
+            },
+            RulesError::UnknownRule { line, key } => {
+                write!(f, "line {}: unknown rule '{}'", line, key)
+            },
+            RulesError::InvalidNumber { line, value } => {
+                write!(f, "line {}: '{}' isn't a valid number", line, value)
+            }
+        }
+    }
+}
+
+impl Error for RulesError {}
+
 struct LetterScorer {
     points: HashMap<char, u16>,
+    bingo_bonus: u16
 }
 
 impl LetterScorer {
-    fn parse(path: &String) -> LetterScorer {
+    fn new(language: &Language, rules: &Rules) -> LetterScorer {
         let mut score = HashMap::new();
-        let letterpoints = fs::read_to_string(path).unwrap();
 
-        for line in letterpoints.split_terminator("\n") {
-            let (c, points) = line.split_once(",").unwrap();
-
-            score.insert(
-                c.chars().nth(0).unwrap(),
-                points.parse::<u16>().unwrap()
-            );
+        for c in language.alphabet().iter().chain(['?'].iter()) {
+            score.insert(*c, language.points(*c).unwrap());
         }
 
-        LetterScorer { points: score }
+        LetterScorer { points: score, bingo_bonus: rules.bingo_bonus }
     }
 
+    // Scores a word with the tiles that are placed for it from the rack, the
+    // rest of the word has to be on the board already.
     fn score_with_board(&self,
-                        word: &String,
-                        letters: &String,
+                        word: &str,
+                        placed_tiles: &[PlacedTile],
                         board: &ParsedBoard,
-                        direction: char,
-                        (x, y): (usize, usize)) -> u16 {
+                        direction: Direction,
+                        (x, y): (usize, usize)) -> Result<u16, PlacementError> {
 
         let mut total_points = 0;
-        let mut points_letters = letters.replace("?", "");
-        let range = 0..word.len();
-
-        let tiles: Vec<&Tile> = match direction {
-            'H' => {
-                let start_row = &board.tiles[y];
-                range.map(|i| &start_row[x + i]).collect()
-            },
-            'V' => range.map(|i| &board.tiles[y + i][x]).collect(),
-            _   => panic!("Invalid direction")
-        };
+        let mut word_multiplier = 1;
+        let mut tiles_placed = 0;
 
         for (i, w) in word.chars().enumerate() {
-            if points_letters.contains(w) {
-                let multiplier = match tiles[i] {
-                    Tile::DoubleLetter => 2,
-                    Tile::TripleLetter => 3,
-                    _ => 1
-                };
-
-                total_points += self.points[&w] * multiplier;
-                points_letters = points_letters.replacen(&w.to_string(), "", 1);
+            let position = match direction {
+                Direction::Horizontal => (x + i, y),
+                Direction::Vertical => (x, y + i)
+            };
+
+            let tile = board
+                .tile_at(position.0, position.1)
+                .ok_or(PlacementError::OutOfBounds { position })?;
+
+            // Letters that are already on the board count at face value, the
+            // bonus tiles underneath them have been used up. Blanks on the
+            // board are worth nothing.
+            match tile {
+                Tile::Letter(_) => {
+                    total_points += self.letter_points(w)?;
+                    continue
+                },
+                Tile::Blank(_) => continue,
+                _ => {}
             }
-        }
 
-        if tiles.contains(&&Tile::DoubleWord) {
-            total_points *= 2;
+            // A new tile from the rack, a blank is worth nothing
+            let blank = placed_tiles
+                .iter()
+                .any(|placed_tile| placed_tile.position == position && placed_tile.blank);
+            let letter_points = if blank {
+                0
+            } else {
+                self.letter_points(w)?
+            };
+
+            let letter_multiplier = match tile {
+                Tile::DoubleLetter => 2,
+                Tile::TripleLetter => 3,
+                _ => 1
+            };
+
+            word_multiplier *= match tile {
+                Tile::DoubleWord => 2,
+                Tile::TripleWord => 3,
+                _ => 1
+            };
+
+            total_points += letter_points * letter_multiplier;
+            tiles_placed += 1;
         }
 
-        if tiles.contains(&&Tile::TripleWord) {
-            total_points *= 3;
-        }
+        Ok(total_points * word_multiplier + self.bingo(tiles_placed))
+    }
 
-        total_points
+    fn letter_points(&self, c: char) -> Result<u16, PlacementError> {
+        self.points.get(&c).cloned().ok_or(PlacementError::UnknownLetter(c))
     }
 
-    fn score(&self, word: &String, letters: &String) -> u16 {
+    // Using all the tiles of a full rack in a single play earns a bonus
+    fn bingo(&self, tiles_placed: usize) -> u16 {
+        if tiles_placed == RACK_SIZE {
+            self.bingo_bonus
+        } else {
+            0
+        }
+    }
+
+    fn score(&self, word: &str, letters: &str) -> u16 {
         let mut total_points = 0;
         let mut points_letters = letters.replace("?", "");
 
         for w in word.chars() {
             if points_letters.contains(w) {
                 total_points += self.points[&w];
-                points_letters = points_letters.replacen(&w.to_string(), "", 1);
+                points_letters = points_letters.replacen(w, "", 1);
             }
         }
-        total_points
+        total_points + self.bingo(word.chars().count())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::fs;
-    use std::path::Path;
-    use serial_test::serial;
-    use crate::dictionary_generator::generate;
+    use crate::dictionary_generator::{generate, read_language};
+
+    // Scores a word the way it's played, with the blanks where they're worth
+    // the most
+    fn score_on_board(letter_scorer: &LetterScorer,
+                      word: &str,
+                      letters: &str,
+                      board: &ParsedBoard,
+                      direction: Direction,
+                      position: (usize, usize)) -> Result<u16, PlacementError> {
+
+        let slot = board.slot(position, direction, word)?;
+        let scores = slot
+            .placements(word, letters)?
+            .iter()
+            .map(|placed_tiles| {
+                letter_scorer.score_with_board(word, placed_tiles, board, direction, position)
+            })
+            .collect::<Result<Vec<u16>, PlacementError>>()?;
+
+        Ok(scores.into_iter().max().unwrap())
+    }
 
     #[test]
-    #[serial]
     fn test_anagrams_teers() {
-        let db_file = String::from("data/test/dictionary.sqlite");
-        if Path::new(&db_file).is_file() {
-            fs::remove_file(db_file).unwrap();
-        }
-
         let base_path = String::from("data/test");
         let letters = String::from("TEERS");
-        let dictionary = generate(base_path);
-        let lp_path = String::from("data/test/letterpoints.txt");
+        let dictionary = generate(base_path, None);
         let layout_path = String::from("layout.default.board");
         let current_board_path = String::from("current.board");
 
         let board = Board::new(
             &letters,
             &dictionary,
-            &lp_path,
             &layout_path,
-            &current_board_path
-        );
-
-        assert_eq!(board.anagrams(), vec![
-            String::from("EERST"),
-            String::from("ESTER"),
-            String::from("RESET"),
-            String::from("EET"),
-            String::from("ER")
-        ]);
+            &current_board_path,
+            &Rules::default(),
+            Ranking::default()
+        ).unwrap();
+
+        let anagrams = board.anagrams();
+        let words: Vec<&str> = anagrams.iter().map(|a| a.word.as_str()).collect();
+        assert_eq!(words, vec!["EERST", "ESTER", "RESET", "EET", "ER"]);
+        assert!(anagrams.iter().all(|a| a.blanks.is_empty()));
     }
 
     #[test]
-    #[serial]
     fn test_anagrams_joker() {
-        let db_file = String::from("data/test/dictionary.sqlite");
-        if Path::new(&db_file).is_file() {
-            fs::remove_file(db_file).unwrap();
-        }
-
         let base_path = String::from("data/test");
         let letters = String::from("T??RS");
-        let dictionary = generate(base_path);
-        let lp_path = String::from("data/test/letterpoints.txt");
+        let dictionary = generate(base_path, None);
         let layout_path = String::from("layout.default.board");
         let current_board_path = String::from("current.board");
 
         let board = Board::new(
             &letters,
             &dictionary,
-            &lp_path,
             &layout_path,
-            &current_board_path
+            &current_board_path,
+            &Rules::default(),
+            Ranking::default()
+        ).unwrap();
+
+        let anagrams = board.anagrams();
+        let words: Vec<&str> = anagrams.iter().map(|a| a.word.as_str()).collect();
+        assert_eq!(words, vec![
+            "EERST", "ESTER", "RESET", "STAAR", "STEUR", "EET", "ER", "MN", "ZE"
+        ]);
+        assert_eq!(anagrams[3].to_string(), "STAAR, blank as A, blank as A");
+
+        let plays = board.optimal_plays();
+
+        // The best opening plays hit one of the double words
+        assert_eq!(plays[0].points, 12);
+        assert_eq!(find_play(&plays, "EERST", (7, 7), Direction::Horizontal).points, 12);
+        assert_eq!(find_play(&plays, "EERST", (3, 7), Direction::Horizontal).points, 12);
+
+        // Downwards, the S can be put on a double letter
+        assert_eq!(find_play(&plays, "STAAR", (7, 3), Direction::Vertical).points, 8);
+
+        // Both blanks are used as an E
+        let play = find_play(&plays, "EERST", (7, 7), Direction::Horizontal);
+        assert_eq!(
+            play.blanks(),
+            vec![
+                &PlacedTile { position: (7, 7), letter: 'E', blank: true },
+                &PlacedTile { position: (8, 7), letter: 'E', blank: true }
+            ]
         );
+        assert_eq!(play.leave, String::new());
 
-        assert_eq!(board.anagrams(), vec![
-            String::from("EERST"),
-            String::from("ESTER"),
-            String::from("RESET"),
-            String::from("STAAR"),
-            String::from("STEUR"),
-            String::from("EET"),
-            String::from("ER"),
-            String::from("MN"),
-            String::from("ZE")
-        ]);
+        // Only using part of the rack leaves the rest
+        let play = find_play(&plays, "ER", (7, 7), Direction::Horizontal);
+        assert_eq!(play.leave, String::from("T?S"));
 
-        assert_eq!(board.optimal_plays(), vec![
-            Play { word: String::from("EERST"), position: (7, 7), points: 12 },
-            Play { word: String::from("EERST"), position: (3, 7), points: 12 },
-        ]);
+        // Every play covers the start tile
+        assert!(plays.iter().all(|play| play.position.0 == 7 || play.position.1 == 7));
+        assert!(plays.windows(2).all(|w| w[0].points >= w[1].points));
     }
 
     #[test]
     fn test_parse_board() {
         let layout_path = String::from("layout.default.board");
         let current_board_path = String::from("current.board");
-        let board = ParsedBoard::parse(&layout_path, &current_board_path);
+        let board = ParsedBoard::parse(&layout_path, &current_board_path).unwrap();
 
         assert_eq!(board.tiles.len(), 15);
         assert_eq!(board.tiles[0].len(), 15);
-        assert_eq!(board.is_opening_turn(), true);
+        assert!(board.is_opening_turn());
+    }
+
+    #[test]
+    fn test_invalid_boards() {
+        let cases = [
+            ("..\n.6\n", "", BoardError::InvalidTile { line: 2, column: 2, tile: '6' }),
+            ("..\n..\n", "...\n..A\n", BoardError::OffTheBoard { line: 2, column: 3, letter: 'A' }),
+            ("..\n..\n", "..\n..\nA\n", BoardError::OffTheBoard { line: 3, column: 1, letter: 'A' }),
+            ("..\n..\n", ".#\n", BoardError::InvalidLetter { line: 1, column: 2, letter: '#' }),
+            ("..\n..\n", "ß\n", BoardError::InvalidLetter { line: 1, column: 1, letter: 'ß' })
+        ];
+
+        for (layout, current_board, error) in cases {
+            assert_eq!(ParsedBoard::from_contents(layout, current_board).err(), Some(error));
+        }
+
+        assert_eq!(
+            BoardError::OffTheBoard { line: 16, column: 1, letter: 'A' }.to_string(),
+            "line 16, column 1: the letter 'A' is off the board"
+        );
     }
 
     #[test]
     fn test_parse_board_not_opening() {
         let layout_path = String::from("layout.default.board");
         let current_board_path = String::from("data/test/test_simple.board");
-        let board = ParsedBoard::parse(&layout_path, &current_board_path);
+        let board = ParsedBoard::parse(&layout_path, &current_board_path).unwrap();
 
         assert_eq!(board.tiles.len(), 15);
         assert_eq!(board.tiles[0].len(), 15);
-        assert_eq!(board.is_opening_turn(), false);
+        assert!(!board.is_opening_turn());
     }
 
     #[test]
     fn test_score_word_with_empty_board() {
         let layout_path = String::from("layout.default.board");
         let current_board_path = String::from("current.board");
-        let board = ParsedBoard::parse(&layout_path, &current_board_path);
-        let lp_path = String::from("data/test/letterpoints.txt");
-        let letter_scorer = LetterScorer::parse(&lp_path);
+        let board = ParsedBoard::parse(&layout_path, &current_board_path).unwrap();
+        let letter_scorer = LetterScorer::new(&read_language(&String::from("data/test")), &Rules::default());
 
         // Hits no special tiles
         let word = String::from("TEST");
         let letters = String::from("TEST");
-        let score = letter_scorer.score_with_board(&word, &letters, &board, 'H', (7, 7));
-        assert_eq!(score, 7);
+        let score = score_on_board(&letter_scorer, &word, &letters, &board, Direction::Horizontal, (7, 7));
+        assert_eq!(score, Ok(7));
 
         // Hits a double letter
         let word = String::from("ZOUTIG");
         let letters = String::from("ZOUTIG");
-        let score = letter_scorer.score_with_board(&word, &letters, &board, 'H', (7, 7));
-        assert_eq!(score, 30);
+        let score = score_on_board(&letter_scorer, &word, &letters, &board, Direction::Horizontal, (7, 7));
+        assert_eq!(score, Ok(30));
 
         // Hits a double and triple letter
         let word = String::from("ZOUTIG");
         let letters = String::from("ZOUTIG");
-        let score = letter_scorer.score_with_board(&word, &letters, &board, 'H', (0, 4));
-        assert_eq!(score, 90);
+        let score = score_on_board(&letter_scorer, &word, &letters, &board, Direction::Horizontal, (0, 4));
+        assert_eq!(score, Ok(90));
+
+        // Hits two double words, which counts four times, and uses all
+        // seven tiles for the bingo bonus
+        let word = String::from("ABCDEFG");
+        let letters = String::from("ABCDEFG");
+        let score = score_on_board(&letter_scorer, &word, &letters, &board, Direction::Horizontal, (4, 4));
+        assert_eq!(score, Ok(144));
+
+        // A blank scores nothing, not even on a double letter
+        let word = String::from("ZOUTIG");
+        let letters = String::from("ZOUTI?");
+        let score = score_on_board(&letter_scorer, &word, &letters, &board, Direction::Horizontal, (7, 7));
+        assert_eq!(score, Ok(24));
+
+        // A word that runs off the board
+        let word = String::from("ZOUTIG");
+        let letters = String::from("ZOUTIG");
+        let score = score_on_board(&letter_scorer, &word, &letters, &board, Direction::Vertical, (3, 12));
+        assert_eq!(score, Err(PlacementError::OutOfBounds { position: (3, 15) }));
+
+        // A letter that has no points
+        let word = String::from("ÉÉN");
+        let letters = String::from("ÉÉN");
+        let score = score_on_board(&letter_scorer, &word, &letters, &board, Direction::Vertical, (3, 3));
+        assert_eq!(score, Err(PlacementError::UnknownLetter('É')));
+    }
+
+    #[test]
+    fn test_play_at() {
+        let base_path = String::from("data/test");
+        let letters = String::from("REETZ");
+        let dictionary = generate(base_path, None);
+        let layout_path = String::from("layout.default.board");
+        let current_board_path = String::from("data/test/test_simple.board");
+
+        let board = Board::new(
+            &letters,
+            &dictionary,
+            &layout_path,
+            &current_board_path,
+            &Rules::default(),
+            Ranking::default()
+        ).unwrap();
+
+        let play = board.play_at("RESET", (5, 4), Direction::Vertical).unwrap();
+        assert_eq!(play.points, 10);
+        assert_eq!(play.leave, String::from("Z"));
+
+        assert_eq!(
+            board.play_at("RESET", (12, 6), Direction::Horizontal),
+            Err(PlacementError::OutOfBounds { position: (15, 6) })
+        );
+        assert_eq!(
+            board.play_at("RESET", (usize::MAX, 6), Direction::Horizontal),
+            Err(PlacementError::OutOfBounds { position: (usize::MAX, 6) })
+        );
+        assert_eq!(
+            board.play_at("RESET", (6, 4), Direction::Vertical),
+            Err(PlacementError::ConflictingLetter { position: (6, 6), letter: 'S', existing: 'T' })
+        );
+        assert_eq!(
+            board.play_at("STAAR", (5, 6), Direction::Vertical),
+            Err(PlacementError::NotOnRack('A'))
+        );
+        assert_eq!(
+            board.play_at("STEPS", (5, 6), Direction::Horizontal),
+            Err(PlacementError::NoNewTiles)
+        );
+        assert_eq!(
+            board.play_at("ER", (3, 6), Direction::Horizontal),
+            Err(PlacementError::OccupiedEnd { position: (5, 6) })
+        );
+        assert_eq!(
+            board.play_at("ER", (0, 0), Direction::Horizontal),
+            Err(PlacementError::NotConnected)
+        );
+        assert_eq!(
+            board.play_at("TEER", (4, 3), Direction::Vertical),
+            Err(PlacementError::UnknownWord(String::from("TEER")))
+        );
+        assert_eq!(
+            board.play_at("ER", (4, 5), Direction::Vertical),
+            Err(PlacementError::UnknownWord(String::from("RSTEPS")))
+        );
 
-        // Test when a word is out of bounds (so doesn't fit)
-        // Test double letter and triple letter
-        // Test vertical words
-        // Test when there's already letters in the grid
+        let current_board_path = String::from("current.board");
+        let board = Board::new(
+            &letters,
+            &dictionary,
+            &layout_path,
+            &current_board_path,
+            &Rules::default(),
+            Ranking::default()
+        ).unwrap();
+        assert_eq!(
+            board.play_at("EET", (0, 0), Direction::Horizontal),
+            Err(PlacementError::MissesStart)
+        );
+        assert!(board.play_at("EET", (7, 7), Direction::Horizontal).is_ok());
+        assert_eq!(
+            PlacementError::OutOfBounds { position: (15, 6) }.to_string(),
+            "The word runs off the board at (15, 6)"
+        );
+    }
+
+    #[test]
+    fn test_play_at_with_blank() {
+        let base_path = String::from("data/test");
+        let letters = String::from("TAR?");
+        let dictionary = generate(base_path, None);
+        let layout_path = String::from("layout.default.board");
+        let current_board_path = String::from("data/test/test_simple.board");
+
+        let board = Board::new(
+            &letters,
+            &dictionary,
+            &layout_path,
+            &current_board_path,
+            &Rules::default(),
+            Ranking::default()
+        ).unwrap();
+
+        // STAAR down from the S of STEPS, the blank goes on the A that isn't
+        // on the triple letter
+        let play = board.play_at("STAAR", (5, 6), Direction::Vertical).unwrap();
+        assert_eq!(play.points, 9);
+        assert_eq!(play.blanks(), vec![&PlacedTile { position: (5, 8), letter: 'A', blank: true }]);
+        assert_eq!(play.leave, String::from(""));
+    }
+
+    #[test]
+    fn test_score_word_with_letters_on_board() {
+        let layout_path = String::from("layout.default.board");
+        let current_board_path = String::from("data/test/test_simple.board");
+        let board = ParsedBoard::parse(&layout_path, &current_board_path).unwrap();
+        let letter_scorer = LetterScorer::new(&read_language(&String::from("data/test")), &Rules::default());
+
+        // RESET through the S of STEPS, the S counts at face value
+        let word = String::from("RESET");
+        let letters = String::from("REET");
+        let score = score_on_board(&letter_scorer, &word, &letters, &board, Direction::Vertical, (5, 4));
+        assert_eq!(score, Ok(10));
+
+        // Same goes for a blank on the rack
+        let word = String::from("RESET");
+        let letters = String::from("R?ET");
+        let score = score_on_board(&letter_scorer, &word, &letters, &board, Direction::Vertical, (5, 4));
+        assert_eq!(score, Ok(9));
+
+        // A blank on the board is worth nothing
+        let current_board_path = String::from("data/test/test_blank.board");
+        let board = ParsedBoard::parse(&layout_path, &current_board_path).unwrap();
+        let word = String::from("EET");
+        let letters = String::from("ET");
+        let score = score_on_board(&letter_scorer, &word, &letters, &board, Direction::Vertical, (7, 5));
+        assert_eq!(score, Ok(3));
+    }
+
+    #[test]
+    fn test_optimal_plays_non_ascii() {
+        let base_path = String::from("data/test-no");
+        let letters = String::from("BLÅ?");
+        let dictionary = generate(base_path, None);
+        let layout_path = String::from("layout.default.board");
+        let current_board_path = String::from("data/test-no/test.board");
+
+        let board = Board::new(
+            &letters,
+            &dictionary,
+            &layout_path,
+            &current_board_path,
+            &Rules::default(),
+            Ranking::default()
+        ).unwrap();
+
+        assert_eq!(board.parsed_board.tiles[7][6], Tile::Blank('Æ'));
+
+        // BLÅ in front of the BæR that's on the board, with the L on a double
+        // word, the blank isn't needed
+        let plays = board.optimal_plays();
+        let play = find_play(&plays, "BLÅBÆR", (2, 7), Direction::Horizontal);
+        assert_eq!(play.points, 28);
+        assert_eq!(play.leave, String::from("?"));
+
+        let anagrams = board.anagrams();
+        let words: Vec<&str> = anagrams.iter().map(|a| a.word.as_str()).collect();
+        assert_eq!(words, vec!["LÅR", "RÅL", "ÅL", "ÅR", "ØL"]);
+    }
+
+    #[test]
+    fn test_ranking_by_commonness() {
+        let letters = String::from("LØRÅ");
+        let dictionary = generate(String::from("data/test-no"), None);
+        let layout_path = String::from("layout.default.board");
+        let current_board_path = String::from("data/test-no/test.board");
+        let ranking = Ranking { by_commonness: true, min_commonness: 50 };
+
+        let board = Board::new(
+            &letters,
+            &dictionary,
+            &layout_path,
+            &current_board_path,
+            &Rules::default(),
+            ranking
+        ).unwrap();
+
+        let anagrams = board.anagrams();
+        let words: Vec<&str> = anagrams.iter().map(|a| a.word.as_str()).collect();
+        assert_eq!(words, vec!["ÅR", "ØL", "LÅR"]);
+        assert_eq!(anagrams[1].to_string(), "ØL, commonness 90");
+
+        // RÅL and ÅL aren't common enough, and the most common word comes
+        // first even though it scores less
+        let plays = board.optimal_plays();
+        assert!(plays.iter().all(|play| play.word != "RÅL" && play.word != "ÅL"));
+        assert_eq!(plays[0].word, "ÅR");
+        assert_eq!(plays[0].commonness, Some(100));
+        assert!(plays.iter().any(|play| play.word == "LÅR"));
+    }
+
+    #[test]
+    fn test_hookable_words() {
+        let letters = String::new();
+        let dictionary = generate(String::from("data/test"), None);
+        let layout_path = String::from("layout.default.board");
+        let current_board_path = String::from("data/test/test_hooks.board");
+
+        let board = Board::new(
+            &letters,
+            &dictionary,
+            &layout_path,
+            &current_board_path,
+            &Rules::default(),
+            Ranking::default()
+        ).unwrap();
+
+        // ERST can only be hooked in front, which is off the board
+        let hookable = board.hookable_words();
+        assert_eq!(hookable.len(), 1);
+        assert_eq!(hookable[0].position, (5, 7));
+        assert_eq!(hookable[0].direction, Direction::Horizontal);
+        assert_eq!(hookable[0].hooks.words(), vec!["EET"]);
+        assert_eq!(
+            hookable[0].to_string(),
+            "ET at (5, 7) horizontal: front hooks E, back hooks -, makes EET"
+        );
+        assert_eq!(dictionary.hooks("ERST").front, vec!['E']);
+    }
+
+    #[test]
+    fn test_parse_board_with_blanks() {
+        let layout_path = String::from("layout.default.board");
+        let current_board_path = String::from("data/test/test_blank.board");
+        let board = ParsedBoard::parse(&layout_path, &current_board_path).unwrap();
+
+        assert_eq!(board.tiles[6][6], Tile::Letter('T'));
+        assert_eq!(board.tiles[6][7], Tile::Blank('E'));
+        assert_eq!(board.letter_at(7, 6), Some('E'));
+        assert!(!board.is_opening_turn());
     }
 
     #[test]
-    fn test_score_word_with_full_board() {
+    fn test_anchors_and_cross_checks_with_full_board() {
         let layout_path = String::from("layout.default.board");
         let current_board_path = String::from("data/test/test_complex.board");
-        let board = ParsedBoard::parse(&layout_path, &current_board_path);
-        let lp_path = String::from("data/test/letterpoints.txt");
-        let letter_scorer = LetterScorer::parse(&lp_path);
+        let board = ParsedBoard::parse(&layout_path, &current_board_path).unwrap();
+        let dictionary = generate(String::from("data/test"), None);
 
-        board.play_points();
-        // Hits no special tiles
+        // Right after MUFS, but not on top of it or far away from it
+        assert!(board.is_anchor((9, 7), false));
+        assert!(!board.is_anchor((6, 7), false));
+        assert!(!board.is_anchor((0, 0), false));
+
+        // No letter joins BAROK and NEVELEN into a word
+        let cross_checks = board.cross_checks(Direction::Vertical, &dictionary);
+        assert_eq!(cross_checks.get(&(5, 12)), Some(&vec![]));
+
+        // There's nothing above or below it, so anything goes
+        let cross_checks = board.cross_checks(Direction::Horizontal, &dictionary);
+        assert_eq!(cross_checks.get(&(5, 12)), None);
+    }
+
+    #[test]
+    fn test_optimal_plays_not_opening() {
+        let base_path = String::from("data/test");
+        let letters = String::from("REET");
+        let dictionary = generate(base_path, None);
+        let layout_path = String::from("layout.default.board");
+        let current_board_path = String::from("data/test/test_simple.board");
+
+        let board = Board::new(
+            &letters,
+            &dictionary,
+            &layout_path,
+            &current_board_path,
+            &Rules::default(),
+            Ranking::default()
+        ).unwrap();
+
+        let plays = board.optimal_plays();
+
+        // RESET downwards through the S of STEPS
+        assert_eq!(
+            find_play(&plays, "RESET", (5, 4), Direction::Vertical),
+            &Play {
+                word: String::from("RESET"),
+                points: 10,
+                position: (5, 4),
+                direction: Direction::Vertical,
+                placed_tiles: vec![
+                    PlacedTile { position: (5, 4), letter: 'R', blank: false },
+                    PlacedTile { position: (5, 5), letter: 'E', blank: false },
+                    PlacedTile { position: (5, 7), letter: 'E', blank: false },
+                    PlacedTile { position: (5, 8), letter: 'T', blank: false }
+                ],
+                cross_words: vec![],
+                leave: String::new(),
+                commonness: None
+            }
+        );
+
+        // The best plays come first
+        assert!(plays.windows(2).all(|w| w[0].points >= w[1].points));
+
+        // ER right below the S and T of STEPS forms "SE" and "TR"
+        assert!(!plays.iter().any(|play| play.position == (5, 7)));
+    }
+
+    #[test]
+    fn test_optimal_plays_with_cross_words() {
+        let base_path = String::from("data/test");
+        let letters = String::from("ER");
+        let dictionary = generate(base_path, None);
+        let layout_path = String::from("layout.default.board");
+        let current_board_path = String::from("data/test/test_cross.board");
+
+        let board = Board::new(
+            &letters,
+            &dictionary,
+            &layout_path,
+            &current_board_path,
+            &Rules::default(),
+            Ranking::default()
+        ).unwrap();
+
+        let plays = board.optimal_plays();
+        let play = find_play(&plays, "ER", (7, 8), Direction::Horizontal);
+
+        // The E forms EET downwards
+        assert_eq!(
+            play.cross_words,
+            vec![CrossWord { word: String::from("EET"), position: (7, 7), points: 4 }]
+        );
+        assert_eq!(play.points, 7);
+        assert_eq!(
+            play.to_string(),
+            "ER at (7, 8) horizontal for 7 points, EET for 4 points, leaves \"\""
+        );
+    }
+
+    fn find_play<'a>(plays: &'a [Play],
+                     word: &str,
+                     position: (usize, usize),
+                     direction: Direction) -> &'a Play {
+        plays
+            .iter()
+            .find(|play| {
+                play.word == word &&
+                    play.position == position &&
+                    play.direction == direction
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_cross_word() {
+        let layout_path = String::from("layout.default.board");
+        let current_board_path = String::from("data/test/test_complex.board");
+        let board = ParsedBoard::parse(&layout_path, &current_board_path).unwrap();
+
+        // An S after BAROK in a vertical word joins BAROK and NEVELEN
+        let tile = PlacedTile { position: (5, 12), letter: 'S', blank: false };
+        assert_eq!(
+            board.cross_word(Direction::Vertical, &tile),
+            Some((String::from("BAROKSNEVELEN"), (0, 12)))
+        );
+
+        // There are no letters above or below it
+        assert_eq!(board.cross_word(Direction::Horizontal, &tile), None);
+
+        // An E between DESK and the C of ECHODE
+        let tile = PlacedTile { position: (4, 13), letter: 'E', blank: false };
+        assert_eq!(
+            board.cross_word(Direction::Horizontal, &tile),
+            Some((String::from("DESKEC"), (4, 9)))
+        );
+
+        // No letters around it, no cross word
+        let tile = PlacedTile { position: (0, 0), letter: 'E', blank: false };
+        assert_eq!(board.cross_word(Direction::Horizontal, &tile), None);
     }
 
     #[test]
     fn test_score_word() {
-        let lp_path = String::from("data/test/letterpoints.txt");
-        let letter_scorer = LetterScorer::parse(&lp_path);
+        let letter_scorer = LetterScorer::new(&read_language(&String::from("data/test")), &Rules::default());
 
         // Regular word
         let word = String::from("TEST");
@@ -471,5 +1731,51 @@ mod tests {
         let word = String::from("PEST");
         let letters = String::from("TESP?");
         assert_eq!(letter_scorer.score(&word, &letters), 9);
+
+        // Using all seven tiles
+        let word = String::from("ZWIJNEN");
+        let letters = String::from("ZWIJNEN");
+        assert_eq!(letter_scorer.score(&word, &letters), 59);
+
+        // Using all seven tiles, with a different bonus
+        let rules = Rules::parse(&String::from("data/test/rules.txt")).unwrap();
+        let letter_scorer = LetterScorer::new(&read_language(&String::from("data/test")), &rules);
+        assert_eq!(letter_scorer.score(&word, &letters), 69);
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let rules = Rules::parse(&String::from("data/test/rules.txt")).unwrap();
+        assert_eq!(rules, Rules { bingo_bonus: 50 });
+
+        let rules = Rules::from_contents("# Rules of the game\n\nbingo_bonus, 30\n").unwrap();
+        assert_eq!(rules, Rules { bingo_bonus: 30 });
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let cases = [
+            (
+                "bingo_bonus\n",
+                RulesError::InvalidLine { line: 1, content: String::from("bingo_bonus") }
+            ),
+            (
+                "\nbingo_bonus,fifty\n",
+                RulesError::InvalidNumber { line: 2, value: String::from("fifty") }
+            ),
+            (
+                "# comment\nbonus,50\n",
+                RulesError::UnknownRule { line: 2, key: String::from("bonus") }
+            )
+        ];
+
+        for (contents, error) in cases {
+            assert_eq!(Rules::from_contents(contents), Err(error));
+        }
+
+        assert_eq!(
+            RulesError::InvalidNumber { line: 2, value: String::from("fifty") }.to_string(),
+            "line 2: 'fifty' isn't a valid number"
+        );
     }
 }
@@ -1,315 +1,1313 @@
-use rusqlite::Connection;
-use std::collections::HashSet;
-use std::fs::File;
+use crate::gaddag::{Anagram, Gaddag, Hooks};
+use crate::hunspell;
+use crate::language::Language;
+use crate::pattern::{self, PatternError};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::fs::{File, OpenOptions};
 use std::io::{BufReader, BufRead};
 use std::path::Path;
 
-const ASCII_OFFSET: u8 = 65; // the letter 'A'
-const LETTER_COUNT: usize = 26;
 const BOARD_SIZE: usize = 15;
-const BATCH_SIZE: usize = 1000;
+// Bumped whenever the layout of the database changes, the dictionaries that
+// were generated with another version are built again.
+const SCHEMA_VERSION: u32 = 1;
+// Words that are added to, and blocked from, the wordlist of a language
+const EXTRA_FILE: &str = "extra.txt";
+const BLOCKED_FILE: &str = "blocked.txt";
+// The name of the dictionary that's read from `wordlist.txt`
+const DEFAULT_NAME: &str = "default";
+// How often words are used, as `word count` on every line
+const FREQUENCIES_FILE: &str = "frequencies.txt";
 
-fn is_prime(number: u8) -> bool {
-    if number < 2 {
-        return false
+// Why a word from the wordlist can't be played.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Rejection {
+    UnknownLetter(char),
+    TooShort,
+    TooLong
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rejection::UnknownLetter(c) => write!(f, "'{}' isn't in the alphabet", c),
+            Rejection::TooShort => write!(f, "it's shorter than 2 letters"),
+            Rejection::TooLong => write!(f, "it's longer than {} letters", BOARD_SIZE)
+        }
+    }
+}
+
+// Brings a word from the wordlist in the shape it's stored in, or tells why
+// it's a word that can't be played.
+pub fn normalize(word: &str, language: &Language) -> Result<String, Rejection> {
+    let cased_word = language.normalize(word);
+
+    // Skip all the words with chars in them that aren't in the alphabet and
+    // the one's that are over the length
+    if let Some(c) = cased_word.chars().find(|c| !language.alphabet().contains(c)) {
+        return Err(Rejection::UnknownLetter(c));
+    }
+
+    let length = cased_word.chars().count();
+    if length < 2 {
+        return Err(Rejection::TooShort);
+    }
+    if length > BOARD_SIZE {
+        return Err(Rejection::TooLong);
     }
 
-    let mut is_prime: bool = true;
-    let end = (number as f64).sqrt().floor() as u8;
+    Ok(cased_word)
+}
+
+// Reads the `language.txt` manifest of a language folder, the language can't
+// be used at all when it isn't valid.
+pub fn read_language(path: &String) -> Language {
+    let language_file = format!("{}/language.txt", path);
+    if !Path::new(&language_file).is_file() {
+        panic!(
+            "The 'language.txt' file doesn't exist at '{}'",
+            language_file
+        );
+    }
+
+    Language::parse(&language_file).unwrap_or_else(|e| {
+        panic!("Invalid language file '{}': {}", language_file, e)
+    })
+}
+
+// Where a dictionary of a language is read from and compiled to. The default
+// one is read from `wordlist.txt`, and a named one like `flexies` from
+// `wordlist.flexies.txt`, which is compiled to `dictionary.flexies.sqlite`
+// and `dictionary.flexies.gaddag`.
+//
+// When there's no plain wordlist, it's read from a Hunspell dictionary like
+// `wordlist.dic` with its affixes in `wordlist.aff` instead.
+struct DictionaryFiles {
+    wordlist: String,
+    affixes: Option<String>,
+    db: String,
+    compiled: String,
+    lock: String
+}
+
+impl DictionaryFiles {
+    fn new(path: &String, name: Option<&str>) -> DictionaryFiles {
+        // The unnamed one is listed as `default`, so it can be picked by that
+        // name as well
+        let suffix = match name {
+            Some(name) if name != DEFAULT_NAME => format!(".{}", name),
+            _ => String::new()
+        };
 
-    for i in 2..end+1 {
-        if number % i == 0 {
-            is_prime = false;
-            break
+        let wordlist = format!("{}/wordlist{}.txt", path, suffix);
+        let hunspell = format!("{}/wordlist{}.dic", path, suffix);
+        let (wordlist, affixes) =
+            if !Path::new(&wordlist).is_file() && Path::new(&hunspell).is_file() {
+                (hunspell, Some(format!("{}/wordlist{}.aff", path, suffix)))
+            } else {
+                (wordlist, None)
+            };
+
+        DictionaryFiles {
+            wordlist,
+            affixes,
+            db: format!("{}/dictionary{}.sqlite", path, suffix),
+            compiled: format!("{}/dictionary{}.gaddag", path, suffix),
+            lock: format!("{}/dictionary{}.lock", path, suffix)
+        }
+    }
+
+    // Only one process at a time gets to generate or remove a dictionary, the
+    // others wait until it's done. The lock is released when the file that's
+    // returned is dropped.
+    fn lock(&self) -> File {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&self.lock)
+            .unwrap_or_else(|e| panic!("Couldn't open the lock '{}': {}", self.lock, e));
+
+        file.lock().unwrap_or_else(|e| panic!("Couldn't lock '{}': {}", self.lock, e));
+        file
+    }
+
+    fn remove_generated(&self) {
+        for file in [&self.db, &self.compiled] {
+            if Path::new(file).is_file() {
+                fs::remove_file(file).unwrap();
+            }
         }
     }
-    is_prime
 }
 
-fn generate_prime_numbers(length: usize) -> Vec<u128> {
-    let mut i = 0;
-    let mut list = vec![];
+// Files are built under a temporary name next to where they end up, and only
+// renamed once they're complete. That way an interrupted build never leaves a
+// file behind that looks finished.
+fn temporary_file(file: &String) -> String {
+    format!("{}.tmp", file)
+}
+
+// The database of one of the dictionaries of a language
+pub fn database_file(path: &String, name: Option<&str>) -> String {
+    DictionaryFiles::new(path, name).db
+}
+
+// The names of the dictionaries of a language, where the default one is
+// called `default`.
+pub fn dictionary_names(path: &String) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(path)
+        .unwrap()
+        .filter_map(|entry| {
+            let file_name = entry.ok()?.file_name().into_string().ok()?;
+            let name = file_name.strip_prefix("wordlist")?;
+            let name = name.strip_suffix(".txt").or_else(|| name.strip_suffix(".dic"))?;
+
+            match name {
+                "" => Some(String::from(DEFAULT_NAME)),
+                name => name.strip_prefix('.').map(String::from)
+            }
+        })
+        .collect();
+
+    names.sort();
+    names.dedup();
+    names
+}
+
+// The FNV-1a hash of a file. It's written to the database, so unlike the
+// hasher of the standard library it has to stay the same between builds.
+fn hash_file(path: &String) -> String {
+    let mut reader = BufReader::new(File::open(path).unwrap());
+    let mut hash: u64 = 0xcbf29ce484222325;
 
     loop {
-        if is_prime(i) {
-            list.push(i as u128);
+        let buffer = reader.fill_buf().unwrap();
+        if buffer.is_empty() {
+            break;
         }
 
-        if list.len() == length {
-            break;
+        for byte in buffer {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
         }
 
-        i += 1;
+        let length = buffer.len();
+        reader.consume(length);
     }
 
-    list
+    format!("{:016x}", hash)
 }
 
-pub struct Dictionary {
-    db_path: String,
-    primes: Vec<u128>
+// What a dictionary was generated from. When it doesn't match what's stored
+// in the database anymore, the dictionary is stale and gets rebuilt.
+#[derive(Debug, Eq, PartialEq)]
+struct Metadata {
+    schema_version: String,
+    wordlist_hash: String,
+    build_options: String,
+    overlay_hash: String,
+    frequency_hash: String
 }
 
-impl Dictionary {
-    fn new(db_path: String) -> Dictionary {
-        let primes = generate_prime_numbers(LETTER_COUNT);
+impl Metadata {
+    fn new(path: &String, files: &DictionaryFiles, language: &Language) -> Metadata {
+        // The files that are optional get a `-` when they don't exist
+        let hash = |file: &str| -> String {
+            let file = format!("{}/{}", path, file);
+            if Path::new(&file).is_file() {
+                hash_file(&file)
+            } else {
+                String::from("-")
+            }
+        };
 
-        Dictionary { db_path: db_path, primes: primes }
+        Metadata {
+            schema_version: SCHEMA_VERSION.to_string(),
+            wordlist_hash: match &files.affixes {
+                Some(affixes) => format!("{},{}", hash_file(&files.wordlist), hash_file(affixes)),
+                None => hash_file(&files.wordlist)
+            },
+            build_options: format!("{};max_length={}", language.normalization(), BOARD_SIZE),
+            overlay_hash: format!("{},{}", hash(EXTRA_FILE), hash(BLOCKED_FILE)),
+            frequency_hash: hash(FREQUENCIES_FILE)
+        }
     }
 
-    pub fn get_anagrams_for(&self, strings: &HashSet<String>) -> Vec<String> {
-        for string in strings {
-            if !self.valid_word(string) {
-                panic!("Invalid letters given: {}", string);
-            }
+    // Whether a database was generated from the same wordlist in the same way
+    // and with the same schema, the overlays can be changed in place.
+    fn same_source(&self, current: &Metadata) -> bool {
+        self.schema_version == current.schema_version &&
+            self.wordlist_hash == current.wordlist_hash &&
+            self.build_options == current.build_options
+    }
+
+    // Databases from before the metadata was stored don't have the table, and
+    // are treated as stale.
+    fn read(db_path: &String) -> Option<Metadata> {
+        if !Path::new(db_path).is_file() {
+            return None;
+        }
+
+        let conn = Connection::open(db_path).ok()?;
+        let value = |key: &str| -> Option<String> {
+            conn.query_row(
+                "SELECT value FROM metadata WHERE key = ?",
+                [key],
+                |row| row.get(0)
+            ).ok()
+        };
+
+        Some(Metadata {
+            schema_version: value("schema_version")?,
+            wordlist_hash: value("wordlist_hash")?,
+            build_options: value("build_options")?,
+            overlay_hash: value("overlay_hash")?,
+            frequency_hash: value("frequency_hash")?
+        })
+    }
+
+    fn write(&self, conn: &Connection) -> Result<(), rusqlite::Error> {
+        for (key, value) in [
+            ("schema_version", &self.schema_version),
+            ("wordlist_hash", &self.wordlist_hash),
+            ("build_options", &self.build_options),
+            ("overlay_hash", &self.overlay_hash),
+            ("frequency_hash", &self.frequency_hash)
+        ] {
+            conn.execute(
+                "INSERT OR REPLACE INTO metadata (key, value) VALUES (?, ?)",
+                [key, value]
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+// Whether a word can be played, with the word in the shape it's stored in.
+#[derive(Debug, Eq, PartialEq)]
+pub enum Check {
+    Valid(String),
+    Unknown(String),
+    Rejected(Rejection)
+}
+
+impl Check {
+    pub fn is_valid(&self) -> bool {
+        matches!(self, Check::Valid(_))
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Check::Valid(word) => write!(f, "{} is valid", word),
+            Check::Unknown(word) => write!(f, "{} isn't in the dictionary", word),
+            Check::Rejected(rejection) => write!(f, "can't be played, {}", rejection)
         }
+    }
+}
 
-        let conn = Connection::open(&self.db_path).unwrap();
-        let prime_factors = self.get_prime_factors(strings);
+// How the lines of the wordlist ended up in the dictionary.
+#[derive(Debug)]
+struct ImportSummary {
+    accepted: usize,
+    duplicates: usize,
+    rejected: Vec<(usize, String, Rejection)>
+}
 
-        let factors = prime_factors
-            .iter()
-            .map(|factor| factor.to_string())
-            .collect::<Vec<String>>()
-            .join(", ");
+impl fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Imported {} words, skipped {} duplicates and {} rejected lines",
+            self.accepted,
+            self.duplicates,
+            self.rejected.len()
+        )
+    }
+}
 
-        let q = format!("SELECT word FROM words WHERE prime_factor IN ({}) ORDER BY word", factors);
-        let mut stmt = conn.prepare(&q).unwrap();
-        let mut rows = stmt.query([]).unwrap();
-        let mut anagrams = vec![];
+pub struct Dictionary {
+    language: Language,
+    gaddag: Gaddag,
+    commonness: HashMap<String, u8>
+}
+
+impl Dictionary {
+    fn new(language: Language, gaddag: Gaddag, commonness: HashMap<String, u8>) -> Dictionary {
+        Dictionary { language, gaddag, commonness }
+    }
+
+    pub fn language(&self) -> &Language {
+        &self.language
+    }
+
+    pub fn gaddag(&self) -> &Gaddag {
+        &self.gaddag
+    }
 
-        while let Some(row) = rows.next().unwrap() {
-            let word: String = row.get(0).unwrap();
-            anagrams.push(word);
+    // All the words that can be made with some or all of the letters of a
+    // rack, with the letters the blanks are used as.
+    pub fn anagrams(&self, letters: &str) -> Vec<Anagram> {
+        if letters.chars().any(|c| !self.alphabet().contains(&c) && c != '?') {
+            panic!("Invalid letters given: {}", letters);
         }
 
+        let mut anagrams = self.gaddag.sub_anagrams(letters);
+        for anagram in &mut anagrams {
+            anagram.commonness = self.commonness(&anagram.word);
+        }
+
+        anagrams.sort_by(|a, b| a.word.cmp(&b.word));
         anagrams
     }
 
-    fn get_prime_factors(&self, strings: &HashSet<String>) -> HashSet<u128> {
-        let mut prime_factors = HashSet::new();
+    // All the words that match a pattern like `?A??E` or `S*ING`, which are
+    // between the lengths. With a rack, only the words that can be made by
+    // filling the wildcards with the letters of the rack are found.
+    pub fn matching(&self,
+                    pattern: &str,
+                    min_length: usize,
+                    max_length: usize,
+                    rack: Option<&str>) -> Result<Vec<String>, PatternError> {
 
-        for string in strings {
-            let base = string.replace("?", "");
-            let factor = self.prime_factor(&base);
+        let tokens = pattern::parse(pattern, self.alphabet())?;
+        let max_length = max_length.min(BOARD_SIZE);
 
-            if base.len() < string.len() {
-                let joker_count = string.len() - base.len();
-                // Determines how many possible joker factors I need to add:
-                let total = LETTER_COUNT.pow(joker_count as u32);
+        Ok(self.gaddag.matching(&tokens, (min_length, max_length), rack))
+    }
 
-                for i in 0..total {
-                    let mut new_factor = factor;
-                    for j in 0..joker_count {
-                        let div = LETTER_COUNT.pow(j as u32);
-                        let p = (i / div) % LETTER_COUNT;
+    // Checks a word the way it would've been imported from the wordlist.
+    pub fn check(&self, word: &str) -> Check {
+        match normalize(word, &self.language) {
+            Ok(word) if self.contains(&word) => Check::Valid(word),
+            Ok(word) => Check::Unknown(word),
+            Err(rejection) => Check::Rejected(rejection)
+        }
+    }
 
-                        new_factor *= self.primes[p];
-                    }
-                    prime_factors.insert(new_factor);
-                }
-            } else {
-                prime_factors.insert(factor);
-            }
+    // How common a word is, from 0 for the words that aren't in the frequency
+    // list to 100 for the most common one. It's `None` when the language
+    // doesn't have a frequency list.
+    pub fn commonness(&self, word: &str) -> Option<u8> {
+        if self.commonness.is_empty() {
+            return None;
         }
 
-        prime_factors
+        Some(*self.commonness.get(word).unwrap_or(&0))
+    }
+
+    pub fn alphabet(&self) -> &[char] {
+        self.language.alphabet()
     }
 
-    fn generated(&self) -> bool {
-        Path::new(&self.db_path).is_file()
+    pub fn contains(&self, word: &str) -> bool {
+        self.gaddag.contains(word)
+    }
+
+    // The letters that can be put in front of or after the word to form
+    // another word.
+    pub fn hooks(&self, word: &str) -> Hooks {
+        self.gaddag.hooks(word)
+    }
+}
+
+// Generates one of the dictionaries of a language, or the default one when
+// there's no name.
+pub fn generate(path: String, name: Option<&str>) -> Dictionary {
+    let files = DictionaryFiles::new(&path, name);
+    let wordlist_file = files.wordlist.clone();
+    if !Path::new(&wordlist_file).is_file() {
+        panic!(
+            "The '{}' file doesn't exist at '{}', the dictionaries of this language are: {}",
+            wordlist_file.rsplit('/').next().unwrap(),
+            wordlist_file,
+            dictionary_names(&path).join(", ")
+        );
+    }
+    if let Some(affixes) = files.affixes.as_ref().filter(|file| !Path::new(file).is_file()) {
+        panic!(
+            "The '{}' file doesn't exist at '{}', it's needed for '{}'",
+            affixes.rsplit('/').next().unwrap(),
+            affixes,
+            wordlist_file
+        );
     }
 
-    fn valid_word(&self, word: &String) -> bool {
-        let mut valid_chars = true;
-        for c in word.chars() {
-            if !('A'..='Z').contains(&c) && c != '?' {
-                valid_chars = false;
-                break;
+    let language = read_language(&path);
+    let compiled_file = files.compiled.clone();
+    let db_file = files.db.clone();
+    let _lock = files.lock();
+
+    // When the wordlist, the way it's read or the schema has changed since the
+    // dictionary was generated, both the database and the compiled dictionary
+    // are stale. The overlays and frequencies are replaced in place when
+    // they're all that changed.
+    let metadata = Metadata::new(&path, &files, &language);
+    match Metadata::read(&db_file) {
+        Some(stored) if stored.same_source(&metadata) => {
+            if stored.overlay_hash != metadata.overlay_hash {
+                update_overlays(&db_file, &path, &language, &metadata).unwrap_or_else(|e| {
+                    panic!("Couldn't update the overlays of '{}': {}", db_file, e)
+                });
+
+                if Path::new(&compiled_file).is_file() {
+                    fs::remove_file(&compiled_file).unwrap();
+                }
+            }
+
+            if stored.frequency_hash != metadata.frequency_hash {
+                update_frequencies(&db_file, &path, &language, &metadata).unwrap_or_else(|e| {
+                    panic!("Couldn't update the frequencies of '{}': {}", db_file, e)
+                });
             }
+        },
+        _ => files.remove_generated()
+    }
+
+    if !Path::new(&db_file).is_file() {
+        // What's left of a build that was interrupted is started over
+        let temporary_db = temporary_file(&db_file);
+        if Path::new(&temporary_db).is_file() {
+            fs::remove_file(&temporary_db).unwrap();
         }
 
-        valid_chars && word.len() <= BOARD_SIZE && word.len() > 1
+        let summary = setup_db(&files, &temporary_db, &path, &language, &metadata)
+            .unwrap_or_else(|e| {
+                panic!("Couldn't import '{}' into '{}': {}", wordlist_file, db_file, e)
+            });
+        fs::rename(&temporary_db, &db_file).unwrap();
+
+        // The skipped lines themselves are in the import log of the database
+        if summary.duplicates + summary.rejected.len() > 0 {
+            eprintln!("{}, `report` lists them with their line numbers", summary);
+        } else {
+            eprintln!("{}", summary);
+        }
     }
 
-    fn setup_db(&self, wordlist_file: &String) {
-        let f = File::open(wordlist_file).unwrap();
-        let reader = BufReader::new(f);
-        let conn = Connection::open(&self.db_path).unwrap();
+    // The compiled dictionary is what's used for all the lookups, so it's
+    // only built once and loaded from disk after that. It's built again when
+    // it can't be read.
+    let loaded = if Path::new(&compiled_file).is_file() {
+        Gaddag::load(&compiled_file)
+            .inspect_err(|e| eprintln!("Building '{}' again, it's invalid: {}", compiled_file, e))
+            .ok()
+    } else {
+        None
+    };
 
-        let mut batch = HashSet::new();
+    let gaddag = loaded.unwrap_or_else(|| {
+        let words = playable_words(&db_file).unwrap_or_else(|e| {
+            panic!("Couldn't read the words from '{}': {}", db_file, e)
+        });
+        let gaddag = Gaddag::from_words(&words);
+        let temporary_compiled = temporary_file(&compiled_file);
+        gaddag.write(&temporary_compiled);
+        fs::rename(&temporary_compiled, &compiled_file).unwrap();
+        gaddag
+    });
+    let commonness = read_commonness(&db_file).unwrap_or_else(|e| {
+        panic!("Couldn't read the frequencies from '{}': {}", db_file, e)
+    });
+    Dictionary::new(language, gaddag, commonness)
+}
 
-        self.create_db_schema(&conn);
+fn setup_db(files: &DictionaryFiles,
+            db_path: &String,
+            path: &String,
+            language: &Language,
+            metadata: &Metadata) -> Result<ImportSummary, rusqlite::Error> {
+    let lines = read_wordlist(files);
+    let mut conn = Connection::open(db_path)?;
+    let mut summary = ImportSummary { accepted: 0, duplicates: 0, rejected: vec![] };
 
-        for line in reader.lines() {
-            match line {
-                Ok(word) => {
-                    let mut cased_word = word.to_uppercase();
-                    cased_word = cased_word.replace("'", "");
+    // Everything goes in a single transaction, so an import that fails
+    // halfway doesn't leave half a dictionary behind
+    let transaction = conn.transaction()?;
+    create_db_schema(&transaction)?;
 
-                    // Skip all the words with non-ASCII chars in them and the one's
-                    // that are over the length
-                    if !self.valid_word(&cased_word) {
-                        continue
-                    };
+    {
+        // We use IGNORE here because the wordlist sometimes contains words
+        // like Aaltjes en aaltjes, which end up being the same word.
+        let mut insert = transaction.prepare(
+            "INSERT OR IGNORE INTO words (word) VALUES (?1)"
+        )?;
+        let mut log = transaction.prepare(
+            "INSERT INTO import_log (line, original, outcome, detail) VALUES (?1, ?2, ?3, ?4)"
+        )?;
 
-                    let product: u128 = self.prime_factor(&cased_word);
-                    batch.insert((cased_word, product));
-                },
-                Err(e) => panic!("Something went wrong reading a line {}", e)
+        for (line_number, word) in lines {
+            if word.trim().is_empty() {
+                continue
             }
 
-            if batch.len() >= BATCH_SIZE {
-                self.insert_batch_to_db(&conn, &mut batch);
+            let cased_word = match normalize(&word, language) {
+                Ok(cased_word) => cased_word,
+                Err(rejection) => {
+                    log.execute(params![line_number, word, "rejected", rejection.to_string()])?;
+                    summary.rejected.push((line_number, word, rejection));
+                    continue
+                }
+            };
+
+            if insert.execute([&cased_word])? == 0 {
+                log.execute(params![line_number, word, "duplicate", cased_word])?;
+                summary.duplicates += 1;
+            } else {
+                // Words that had characters stripped or replaced
+                if word.to_uppercase() != cased_word {
+                    log.execute(params![line_number, word, "normalized", cased_word])?;
+                }
+                summary.accepted += 1;
             }
         }
+    }
 
-        // Do the final batch
-        self.insert_batch_to_db(&conn, &mut batch);
+    import_overlays(&transaction, path, language)?;
+    import_frequencies(&transaction, path, language)?;
+
+    // The metadata goes in last, so a database that wasn't set up all the
+    // way is rebuilt the next time. When the wordlist was imported is only
+    // written here, as the overlays and frequencies are updated in place.
+    metadata.write(&transaction)?;
+    transaction.execute(
+        "INSERT OR REPLACE INTO metadata (key, value) VALUES ('imported_at', datetime('now'))",
+        []
+    )?;
+    transaction.commit()?;
+
+    Ok(summary)
+}
+
+// The lines of the wordlist with their line numbers. A Hunspell dictionary is
+// expanded into all the forms of its words, which get the line number of the
+// word they came from.
+fn read_wordlist(files: &DictionaryFiles) -> Vec<(usize, String)> {
+    if let Some(affixes) = &files.affixes {
+        return hunspell::expand(&files.wordlist, affixes).unwrap_or_else(|e| {
+            panic!("Couldn't read the affixes in '{}': {}", affixes, e)
+        });
     }
 
-    fn create_db_schema(&self, conn: &Connection) {
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS words (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                word VARCHAR(15) NOT NULL UNIQUE,
-                prime_factor BIGINT NOT NULL
-            )",
-            []
-        ).unwrap();
+    let reader = BufReader::new(File::open(&files.wordlist).unwrap());
+    reader
+        .lines()
+        .enumerate()
+        .map(|(i, line)| match line {
+            Ok(word) => (i + 1, word),
+            Err(e) => panic!("Something went wrong reading a line {}", e)
+        })
+        .collect()
+}
 
-        conn.execute(
-            "CREATE INDEX IF NOT EXISTS prime_factor_index ON words (prime_factor)",
-            []
-        ).unwrap();
+fn create_db_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS words (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            word VARCHAR(15) NOT NULL UNIQUE
+        )",
+        []
+    )?;
+
+    create_overlay_schema(conn)?;
+    create_frequency_schema(conn)?;
+    create_import_log_schema(conn)?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS metadata (
+            key VARCHAR(32) PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+        []
+    )?;
+
+    Ok(())
+}
+
+// The words of the overlays are kept apart from the ones of the wordlist, so
+// they can be replaced without importing the wordlist again.
+fn create_overlay_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS extra_words (
+            word VARCHAR(15) PRIMARY KEY
+        )",
+        []
+    )?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS blocked_words (
+            word VARCHAR(15) PRIMARY KEY
+        )",
+        []
+    )?;
+
+    Ok(())
+}
+
+fn import_overlays(conn: &Connection,
+                   path: &String,
+                   language: &Language) -> Result<(), rusqlite::Error> {
+    conn.execute("DELETE FROM extra_words", [])?;
+    conn.execute("DELETE FROM blocked_words", [])?;
+
+    let mut insert = conn.prepare("INSERT OR IGNORE INTO extra_words (word) VALUES (?1)")?;
+    for word in read_overlay(path, EXTRA_FILE, language) {
+        insert.execute([word])?;
     }
 
-    fn insert_batch_to_db(&self, conn: &Connection, batch: &mut HashSet<(String, u128)>) {
-        let values: String = batch
-            .iter()
-            .map(|b| format!("(\"{}\", \"{}\")", b.0, b.1))
-            .collect::<Vec<String>>()
-            .join(",");
-
-        // We use IGNORE here because the wordlist sometimes contains words like
-        // Aaltjes en aaltjes. If "aaltjes" just so happened to be on the next batch,
-        // this can result in a duplicate insert, which individual value(s) we
-        // should just ignore.
-        let query = format!(
-            "INSERT OR IGNORE INTO words (word, prime_factor) VALUES {}",
-            values
-        );
+    let mut insert = conn.prepare("INSERT OR IGNORE INTO blocked_words (word) VALUES (?1)")?;
+    for word in read_overlay(path, BLOCKED_FILE, language) {
+        insert.execute([word])?;
+    }
+
+    Ok(())
+}
+
+fn update_overlays(db_path: &String,
+                   path: &String,
+                   language: &Language,
+                   metadata: &Metadata) -> Result<(), rusqlite::Error> {
+    let mut conn = Connection::open(db_path)?;
+    let transaction = conn.transaction()?;
+
+    import_overlays(&transaction, path, language)?;
+    metadata.write(&transaction)?;
+
+    transaction.commit()
+}
+
+// The words of an overlay file, one per line. Unlike the wordlist, these are
+// picked by hand, so a word in there that can't be played is a mistake.
+fn read_overlay(path: &String, file: &str, language: &Language) -> Vec<String> {
+    let file = format!("{}/{}", path, file);
+    if !Path::new(&file).is_file() {
+        return vec![];
+    }
+
+    fs::read_to_string(&file)
+        .unwrap()
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            normalize(line.trim(), language).unwrap_or_else(|rejection| {
+                panic!("Invalid word on line {} of '{}': {}", i + 1, file, rejection)
+            })
+        })
+        .collect()
+}
+
+// What happened to the lines of the wordlist that didn't go in as they were:
+// the rejected ones with the reason, and the duplicates and normalized ones
+// with the word they became. The forms of a word in a Hunspell dictionary all
+// share its line.
+fn create_import_log_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS import_log (
+            line INTEGER NOT NULL,
+            original TEXT NOT NULL,
+            outcome VARCHAR(16) NOT NULL,
+            detail TEXT NOT NULL
+        )",
+        []
+    )?;
+
+    Ok(())
+}
+
+fn create_frequency_schema(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS frequencies (
+            word VARCHAR(15) PRIMARY KEY,
+            count INTEGER NOT NULL,
+            commonness INTEGER NOT NULL
+        )",
+        []
+    )?;
+
+    Ok(())
+}
+
+// Reads the frequency list of a language, where the counts of the words that
+// end up the same are added up. Frequency lists are usually made from all
+// kinds of text, so the lines that aren't playable words are skipped.
+fn read_frequencies(path: &String, language: &Language) -> HashMap<String, u64> {
+    let file = format!("{}/{}", path, FREQUENCIES_FILE);
+    let mut frequencies = HashMap::new();
+    if !Path::new(&file).is_file() {
+        return frequencies;
+    }
 
-        conn.execute(&query, []).unwrap_or_else(|e| {
-            println!("FAILURE {}", e);
-            0
+    for (i, line) in fs::read_to_string(&file).unwrap().lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue
+        }
+
+        let (word, count) = line
+            .rsplit_once(|c: char| c.is_whitespace() || c == ',')
+            .unwrap_or_else(|| panic!("Invalid line {} of '{}': {}", i + 1, file, line));
+        let count = count.parse::<u64>().unwrap_or_else(|_| {
+            panic!("Invalid count on line {} of '{}': {}", i + 1, file, count)
         });
 
-        batch.clear();
+        if let Ok(word) = normalize(word.trim(), language) {
+            *frequencies.entry(word).or_insert(0) += count;
+        }
     }
 
-    fn prime_factor(&self, word: &String) -> u128 {
-        word
-            .bytes()
-            .map(|c| self.primes[(c - ASCII_OFFSET) as usize])
-            .product()
+    frequencies
+}
+
+// The commonness is the count on a logarithmic scale, where the most common
+// word is 100. Word use falls off steeply, so on a linear scale all but a
+// handful of words would end up close to 0.
+fn commonness(count: u64, max_count: u64) -> u8 {
+    if max_count == 0 {
+        return 0;
     }
+
+    let commonness = 100.0 * ((count + 1) as f64).ln() / ((max_count + 1) as f64).ln();
+    commonness.round() as u8
 }
 
-pub fn generate(path: String) -> Dictionary {
-    let wordlist_file = format!("{}/wordlist.txt", path);
-    if !Path::new(&wordlist_file).is_file() {
-        panic!(
-            "The 'wordlist.txt' file doesn't exist at '{}'",
-            wordlist_file
-        );
+fn import_frequencies(conn: &Connection,
+                      path: &String,
+                      language: &Language) -> Result<(), rusqlite::Error> {
+    conn.execute("DELETE FROM frequencies", [])?;
+
+    let frequencies = read_frequencies(path, language);
+    let max_count = frequencies.values().copied().max().unwrap_or(0);
+    let mut insert = conn.prepare(
+        "INSERT INTO frequencies (word, count, commonness) VALUES (?1, ?2, ?3)"
+    )?;
+
+    for (word, count) in &frequencies {
+        insert.execute(params![word, *count as i64, commonness(*count, max_count)])?;
     }
 
-    let db_file = format!("{}/dictionary.sqlite", path);
-    let dictionary = Dictionary::new(db_file);
+    Ok(())
+}
+
+fn update_frequencies(db_path: &String,
+                      path: &String,
+                      language: &Language,
+                      metadata: &Metadata) -> Result<(), rusqlite::Error> {
+    let mut conn = Connection::open(db_path)?;
+    let transaction = conn.transaction()?;
+
+    import_frequencies(&transaction, path, language)?;
+    metadata.write(&transaction)?;
+
+    transaction.commit()
+}
+
+fn read_commonness(db_path: &String) -> Result<HashMap<String, u8>, rusqlite::Error> {
+    let conn = Connection::open(db_path)?;
+    let mut select = conn.prepare("SELECT word, commonness FROM frequencies")?;
+
+    let rows = select.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
 
-    if dictionary.generated() {
-        return dictionary;
+// The words of the wordlist and the extra words, without the blocked ones.
+pub fn playable_words(db_path: &String) -> Result<Vec<String>, rusqlite::Error> {
+    let conn = Connection::open(db_path)?;
+    let mut select = conn.prepare(
+        "SELECT word FROM words
+        UNION SELECT word FROM extra_words
+        EXCEPT SELECT word FROM blocked_words"
+    )?;
+
+    let words = select.query_map([], |row| row.get(0))?;
+    words.collect()
+}
+
+// Adds words to the extra words of a language, and takes them out of the
+// blocked ones. They're in the dictionary the next time it's generated.
+pub fn add_words(path: &String, words: &[String]) {
+    edit_overlays(path, words, EXTRA_FILE, BLOCKED_FILE);
+}
+
+// Adds words to the blocked words of a language, and takes them out of the
+// extra ones.
+pub fn remove_words(path: &String, words: &[String]) {
+    edit_overlays(path, words, BLOCKED_FILE, EXTRA_FILE);
+}
+
+fn edit_overlays(path: &String, words: &[String], add_to: &str, take_from: &str) {
+    let language = read_language(path);
+    // The overlays are shared by every dictionary of the language, so none of
+    // them may be built while they're rewritten. The locks are always taken in
+    // the same order.
+    let _locks: Vec<File> = dictionary_names(path)
+        .iter()
+        .map(|name| DictionaryFiles::new(path, Some(name)).lock())
+        .collect();
+    let words: Vec<String> = words
+        .iter()
+        .map(|word| {
+            normalize(word, &language).unwrap_or_else(|rejection| {
+                panic!("Can't use '{}': {}", word, rejection)
+            })
+        })
+        .collect();
+
+    let mut added = read_overlay(path, add_to, &language);
+    for word in &words {
+        if !added.contains(word) {
+            added.push(word.clone());
+        }
     }
 
-    dictionary.setup_db(&wordlist_file);
-    dictionary
+    let mut taken = read_overlay(path, take_from, &language);
+    taken.retain(|word| !words.contains(word));
+
+    for (file, words) in [(add_to, added), (take_from, taken)] {
+        let contents: String = words.iter().map(|word| format!("{}\n", word)).collect();
+        fs::write(format!("{}/{}", path, file), contents).unwrap();
+    }
+}
+
+// Removes the generated database and compiled dictionary of a language, so
+// they're built from scratch by `generate`. It waits for a build of the same
+// dictionary that's going on to finish first.
+pub fn remove_generated(path: &String, name: Option<&str>) {
+    let files = DictionaryFiles::new(path, name);
+    let _lock = files.lock();
+
+    files.remove_generated();
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serial_test::serial;
-    use std::fs;
+    use crate::test_dir::TestDir;
 
     #[test]
     #[should_panic]
     fn test_panic_generate() {
         let base_path = String::from("data/does-not-exist");
-        generate(base_path);
+        generate(base_path, None);
     }
 
     #[test]
-    #[serial]
     fn test_success_generate() {
-        // Drop the existing db first
-        let db_file = String::from("data/test/dictionary.sqlite");
-        if Path::new(&db_file).is_file() {
-            fs::remove_file(db_file).unwrap();
-        }
-
         let base_path = String::from("data/test");
-        let dictionary = generate(base_path);
+        let dictionary = generate(base_path, None);
 
-        assert_eq!(dictionary.db_path, String::from("data/test/dictionary.sqlite"));
+        assert!(dictionary.contains("RESET"));
+        assert!(Path::new("data/test/dictionary.sqlite").is_file());
+        assert!(Path::new("data/test/dictionary.gaddag").is_file());
 
         // The 2nd time it fetches it from cache
         let base_path = String::from("data/test");
-        let dictionary = generate(base_path);
-        assert_eq!(dictionary.db_path, String::from("data/test/dictionary.sqlite"));
+        let dictionary = generate(base_path, None);
+        assert!(dictionary.contains("RESET"));
     }
 
     #[test]
-    #[serial]
     fn get_anagrams() {
-        // Drop the existing db first
-        let db_file = String::from("data/test/dictionary.sqlite");
-        if Path::new(&db_file).is_file() {
-            fs::remove_file(db_file).unwrap();
-        }
+        let base_path = String::from("data/test");
+        let dictionary = generate(base_path, None);
+
+        let words = |letters: &str| -> Vec<String> {
+            dictionary
+                .anagrams(letters)
+                .into_iter()
+                .map(|anagram| anagram.word)
+                .collect()
+        };
 
+        assert_eq!(words("XYZ").len(), 0);
+        assert_eq!(words("TEERS"), vec!["EERST", "EET", "ER", "ESTER", "RESET"]);
+        assert_eq!(
+            words("T??RS"),
+            vec!["EERST", "EET", "ER", "ESTER", "MN", "RESET", "STAAR", "STEUR", "ZE"]
+        );
+
+        let anagrams = dictionary.anagrams("T??RS");
+        assert_eq!(anagrams[0].blanks, vec![0, 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid letters given: AB1")]
+    fn get_anagrams_invalid_letters() {
+        let dictionary = generate(String::from("data/test"), None);
+        dictionary.anagrams("AB1");
+    }
+
+    #[test]
+    fn get_matching() {
+        let dictionary = generate(String::from("data/test"), None);
+
+        assert_eq!(
+            dictionary.matching("?E?E?", 2, 15, None),
+            Ok(vec![String::from("RESET")])
+        );
+        assert_eq!(
+            dictionary.matching("E*", 3, 15, None),
+            Ok(vec![String::from("EERST"), String::from("EET"), String::from("ESTER")])
+        );
+        assert_eq!(
+            dictionary.matching("E*", 2, 3, None),
+            Ok(vec![String::from("EET"), String::from("ER")])
+        );
+        assert_eq!(
+            dictionary.matching("E*", 2, 15, Some("TE?")),
+            Ok(vec![String::from("EET"), String::from("ER")])
+        );
+        assert_eq!(
+            dictionary.matching("E1", 2, 15, None),
+            Err(PatternError::UnknownLetter('1'))
+        );
+    }
+
+    #[test]
+    fn check_words() {
+        let dictionary = generate(String::from("data/test"), None);
+
+        assert_eq!(dictionary.check("reset"), Check::Valid(String::from("RESET")));
+        assert_eq!(dictionary.check("m'n"), Check::Valid(String::from("MN")));
+        assert_eq!(dictionary.check("RESETS"), Check::Unknown(String::from("RESETS")));
+        assert_eq!(dictionary.check("het bos"), Check::Rejected(Rejection::UnknownLetter(' ')));
+        assert!(dictionary.check("reset").is_valid());
+        assert!(!dictionary.check("a").is_valid());
+
+        assert_eq!(dictionary.check("reset").to_string(), "RESET is valid");
+        assert_eq!(dictionary.check("resets").to_string(), "RESETS isn't in the dictionary");
+        assert_eq!(
+            dictionary.check("a").to_string(),
+            "can't be played, it's shorter than 2 letters"
+        );
+    }
+
+    #[test]
+    fn lookup_words() {
         let base_path = String::from("data/test");
-        let dictionary = generate(base_path);
+        let dictionary = generate(base_path, None);
+
+        assert!(dictionary.contains("RESET"));
+        assert!(!dictionary.contains("RESETS"));
+    }
+
+    #[test]
+    fn test_rebuild_when_wordlist_changes() {
+        let dir = TestDir::new("rebuild", "data/test");
+        let base_path = dir.path.clone();
+        fs::write(dir.file("wordlist.txt"), "reset\n").unwrap();
+
+        let dictionary = generate(base_path.clone(), None);
+        assert!(dictionary.contains("RESET"));
+        assert!(!dictionary.contains("ESTER"));
+
+        // A new release of the wordlist
+        fs::write(dir.file("wordlist.txt"), "reset\nester\n").unwrap();
+        let dictionary = generate(base_path.clone(), None);
+        assert!(dictionary.contains("ESTER"));
+
+        let metadata = Metadata::read(&dir.file("dictionary.sqlite"));
+        assert_eq!(metadata.unwrap().schema_version, SCHEMA_VERSION.to_string());
+
+        // A database with another schema
+        let conn = Connection::open(dir.file("dictionary.sqlite")).unwrap();
+        conn.execute("UPDATE metadata SET value = '0' WHERE key = 'schema_version'", []).unwrap();
+        conn.execute("DELETE FROM words WHERE word = 'ESTER'", []).unwrap();
+        drop(conn);
+        let dictionary = generate(base_path.clone(), None);
+        assert!(dictionary.contains("ESTER"));
 
-        let word = String::from("XYZ");
-        let mut set = HashSet::new();
-        set.insert(word);
-        assert_eq!(dictionary.get_anagrams_for(&set).len(), 0);
+        // A database from before there was any metadata
+        let conn = Connection::open(dir.file("dictionary.sqlite")).unwrap();
+        conn.execute("DROP TABLE metadata", []).unwrap();
+        drop(conn);
+        fs::write(dir.file("dictionary.gaddag"), "stale").unwrap();
+        let dictionary = generate(base_path.clone(), None);
+        assert!(dictionary.contains("ESTER"));
 
-        let word = String::from("TEERS");
-        let mut set = HashSet::new();
-        set.insert(word);
+        // A compiled dictionary that was cut off
+        let compiled = fs::read(dir.file("dictionary.gaddag")).unwrap();
+        fs::write(dir.file("dictionary.gaddag"), &compiled[..compiled.len() - 8]).unwrap();
+        let dictionary = generate(base_path.clone(), None);
+        assert!(dictionary.contains("ESTER"));
+        assert_eq!(fs::read(dir.file("dictionary.gaddag")).unwrap(), compiled);
+
+        remove_generated(&base_path, None);
+        assert!(!Path::new(&dir.file("dictionary.sqlite")).is_file());
+        assert!(!Path::new(&dir.file("dictionary.gaddag")).is_file());
+    }
+
+    #[test]
+    fn test_overlays() {
+        let dir = TestDir::new("overlay", "data/test");
+        let base_path = dir.path.clone();
+        fs::write(dir.file("wordlist.txt"), "reset\nester\n").unwrap();
+        fs::write(dir.file("extra.txt"), "eerst\n").unwrap();
+
+        let dictionary = generate(base_path.clone(), None);
+        assert!(dictionary.contains("EERST"));
+        assert!(dictionary.contains("ESTER"));
+
+        remove_words(&base_path, &[String::from("ester"), String::from("EERST")]);
+        assert_eq!(fs::read_to_string(dir.file("extra.txt")).unwrap(), "");
         assert_eq!(
-            dictionary.get_anagrams_for(&set),
-            vec![
-                String::from("EERST"),
-                String::from("ESTER"),
-                String::from("RESET")
-            ]
+            fs::read_to_string(dir.file("blocked.txt")).unwrap(),
+            "ESTER\nEERST\n"
         );
 
-        let word = String::from("T??RS");
-        let mut set = HashSet::new();
-        set.insert(word);
+        let dictionary = generate(base_path.clone(), None);
+        assert!(dictionary.contains("RESET"));
+        assert!(!dictionary.contains("ESTER"));
+        assert!(!dictionary.contains("EERST"));
+
+        // The words of the wordlist are still there, they're just blocked
+        let conn = Connection::open(dir.file("dictionary.sqlite")).unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM words", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 2);
+        drop(conn);
+
+        add_words(&base_path, &[String::from("ester"), String::from("reset")]);
+        let dictionary = generate(base_path.clone(), None);
+        assert!(dictionary.contains("ESTER"));
+        assert!(!dictionary.contains("EERST"));
+        assert_eq!(
+            fs::read_to_string(dir.file("blocked.txt")).unwrap(),
+            "EERST\n"
+        );
+    }
+
+    #[test]
+    fn test_frequencies() {
+        let dir = TestDir::new("frequencies", "data/test");
+        let base_path = dir.path.clone();
+        fs::write(dir.file("wordlist.txt"), "reset
+ester
+eerst
+").unwrap();
+
+        let dictionary = generate(base_path.clone(), None);
+        assert_eq!(dictionary.commonness("RESET"), None);
+
+        // Counts of the same word are added up, and the words that can't be
+        // played are skipped
+        fs::write(
+            dir.file("frequencies.txt"),
+            "reset 1000
+Reset,1000
+ester\t99
+sé 5000
+"
+        ).unwrap();
+        let dictionary = generate(base_path.clone(), None);
+        assert_eq!(dictionary.commonness("RESET"), Some(100));
+        assert_eq!(dictionary.commonness("ESTER"), Some(61));
+        assert_eq!(dictionary.commonness("EERST"), Some(0));
+
+        let anagrams = dictionary.anagrams("EERST");
+        assert_eq!(anagrams[0].to_string(), "EERST, commonness 0");
+
+        // Only the frequencies are imported again
+        let conn = Connection::open(dir.file("dictionary.sqlite")).unwrap();
+        conn.execute("INSERT INTO words (word) VALUES ('TREES')", [])
+            .unwrap();
+        drop(conn);
+
+        fs::write(dir.file("frequencies.txt"), "eerst 10
+").unwrap();
+        let dictionary = generate(base_path.clone(), None);
+        assert_eq!(dictionary.commonness("EERST"), Some(100));
+        assert_eq!(dictionary.commonness("RESET"), Some(0));
+
+        let conn = Connection::open(dir.file("dictionary.sqlite")).unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM words", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 4);
+        drop(conn);
+    }
+
+    #[test]
+    fn test_commonness() {
+        assert_eq!(commonness(0, 0), 0);
+        assert_eq!(commonness(0, 1000), 0);
+        assert_eq!(commonness(50, 1000), 57);
+        assert_eq!(commonness(1000, 1000), 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't use 'a': it's shorter than 2 letters")]
+    fn test_add_invalid_word() {
+        add_words(&String::from("data/test"), &[String::from("a")]);
+    }
+
+    #[test]
+    fn test_named_dictionaries() {
+        let dir = TestDir::new("named", "data/test");
+        let base_path = dir.path.clone();
+        fs::write(dir.file("wordlist.txt"), "reset\n").unwrap();
+        fs::write(dir.file("wordlist.flexies.txt"), "reset\nresets\n").unwrap();
+
+        assert_eq!(dictionary_names(&base_path), vec!["default", "flexies"]);
+
+        let default = generate(base_path.clone(), None);
+        let flexies = generate(base_path.clone(), Some("flexies"));
+        assert!(!default.contains("RESETS"));
+        assert!(flexies.contains("RESETS"));
+        assert!(Path::new(&dir.file("dictionary.sqlite")).is_file());
+        assert!(Path::new(&dir.file("dictionary.flexies.sqlite")).is_file());
+        assert!(Path::new(&dir.file("dictionary.flexies.gaddag")).is_file());
+        assert!(!generate(base_path.clone(), Some("default")).contains("RESETS"));
+
+        // Each one is only rebuilt when its own wordlist changes
+        fs::write(dir.file("wordlist.flexies.txt"), "reset\nresets\nester\n").unwrap();
+        let default = generate(base_path.clone(), None);
+        let flexies = generate(base_path.clone(), Some("flexies"));
+        assert!(!default.contains("ESTER"));
+        assert!(flexies.contains("ESTER"));
+
+        remove_generated(&base_path, Some("flexies"));
+        assert!(!Path::new(&dir.file("dictionary.flexies.sqlite")).is_file());
+        assert!(Path::new(&dir.file("dictionary.sqlite")).is_file());
+    }
+
+    #[test]
+    #[should_panic(expected = "the dictionaries of this language are: default")]
+    fn test_panic_unknown_dictionary() {
+        generate(String::from("data/test"), Some("does-not-exist"));
+    }
+
+    #[test]
+    fn test_import_summary() {
+        let dir = TestDir::new("import", "data/test");
+        let base_path = dir.path.clone();
+        fs::write(
+            dir.file("wordlist.txt"),
+            "reset\nRESET\nm'n\n\n\"); DROP TABLE words; --\na\nester\nonoverzichtelijkheid\n"
+        ).unwrap();
+
+        let dictionary = generate(base_path.clone(), None);
+        assert!(dictionary.contains("RESET"));
+        assert!(dictionary.contains("MN"));
+
+        let files = DictionaryFiles::new(&base_path, None);
+        let db_path = files.db.clone();
+        fs::remove_file(&db_path).unwrap();
+        let metadata = Metadata::new(&base_path, &files, &dictionary.language);
+        let summary = setup_db(&files, &db_path, &base_path, &dictionary.language, &metadata)
+            .unwrap();
+
+        assert_eq!(summary.accepted, 3);
+        assert_eq!(summary.duplicates, 1);
         assert_eq!(
-            dictionary.get_anagrams_for(&set),
+            summary.rejected,
             vec![
-                String::from("EERST"),
-                String::from("ESTER"),
-                String::from("RESET"),
-                String::from("STAAR"),
-                String::from("STEUR")
+                (5, String::from("\"); DROP TABLE words; --"), Rejection::UnknownLetter('"')),
+                (6, String::from("a"), Rejection::TooShort),
+                (8, String::from("onoverzichtelijkheid"), Rejection::TooLong)
             ]
         );
+        assert_eq!(
+            summary.to_string(),
+            "Imported 3 words, skipped 1 duplicates and 3 rejected lines"
+        );
+        assert_eq!(Metadata::read(&db_path), Some(metadata));
+    }
+
+    #[test]
+    fn test_hunspell_dictionary() {
+        let dir = TestDir::new("hunspell", "data/test-no");
+        let base_path = dir.path.clone();
+        fs::write(
+            dir.file("wordlist.dic"),
+            "3\nbær/E\nsø/ER\nnaïve/E\n"
+        ).unwrap();
+        fs::write(
+            dir.file("wordlist.aff"),
+            "SET UTF-8\n\nSFX E Y 1\nSFX E 0 ene .\n\nSFX R Y 1\nSFX R 0 r .\n"
+        ).unwrap();
+        assert_eq!(dictionary_names(&base_path), vec!["default"]);
+
+        let dictionary = generate(base_path.clone(), None);
+        for word in ["BÆR", "BÆRENE", "SØ", "SØENE", "SØR"] {
+            assert!(dictionary.contains(word), "{} is missing", word);
+        }
+        assert!(!dictionary.contains("NAÏVENE"));
+
+        // A change to the affixes rebuilds it
+        fs::write(dir.file("wordlist.aff"), "SFX E Y 1\nSFX E 0 er .\n").unwrap();
+        let dictionary = generate(base_path.clone(), None);
+        assert!(dictionary.contains("BÆRER"));
+        assert!(!dictionary.contains("BÆRENE"));
+        assert!(!dictionary.contains("SØR"));
+    }
+
+    #[test]
+    fn test_concurrent_generate() {
+        let dir = TestDir::new("concurrent", "data/test");
+        let base_path = dir.path.clone();
+        fs::write(dir.file("wordlist.txt"), "reset\nester\n").unwrap();
+
+        // What's left of an interrupted build is thrown away
+        fs::write(dir.file("dictionary.sqlite.tmp"), "half a database").unwrap();
+
+        let builds: Vec<_> = (0..4)
+            .map(|_| {
+                let base_path = base_path.clone();
+                std::thread::spawn(move || generate(base_path, None))
+            })
+            .collect();
+
+        for build in builds {
+            let dictionary = build.join().unwrap();
+            assert!(dictionary.contains("RESET"));
+            assert!(dictionary.contains("ESTER"));
+        }
+
+        assert!(!Path::new(&dir.file("dictionary.sqlite.tmp")).exists());
+        assert!(!Path::new(&dir.file("dictionary.gaddag.tmp")).exists());
+    }
+
+    #[test]
+    #[should_panic(expected = "The 'language.txt' file doesn't exist")]
+    fn test_panic_read_language() {
+        read_language(&String::from("data/does-not-exist"));
+    }
+
+    #[test]
+    fn test_normalize() {
+        let language = read_language(&String::from("data/test-no"));
+
+        assert_eq!(normalize("blåbær", &language), Ok(String::from("BLÅBÆR")));
+        assert_eq!(normalize("ø", &language), Err(Rejection::TooShort));
+        assert_eq!(normalize("naïve", &language), Err(Rejection::UnknownLetter('Ï')));
+        assert_eq!(normalize("bær?", &language), Err(Rejection::UnknownLetter('?')));
+        assert_eq!(normalize("blåbærsyltetøyet", &language), Err(Rejection::TooLong));
+
+        let language = read_language(&String::from("data/test"));
+        assert_eq!(normalize("m'n", &language), Ok(String::from("MN")));
+    }
+
+    #[test]
+    fn get_anagrams_non_ascii() {
+        let base_path = String::from("data/test-no");
+        let dictionary = generate(base_path, None);
+
+        let words: Vec<String> = dictionary
+            .anagrams("LØRÅ")
+            .into_iter()
+            .map(|anagram| anagram.word)
+            .collect();
+        assert_eq!(words, vec!["LÅR", "RÅL", "ÅL", "ÅR", "ØL"]);
+
+        assert!(dictionary.contains("BLÅBÆR"));
+        assert!(!dictionary.contains("NAÏVE"));
     }
 }
@@ -0,0 +1,835 @@
+use crate::pattern::Token;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::fs;
+use std::hash::{Hash, Hasher};
+
+// Separates the reversed part of a word from the part that runs forward
+pub const SEPARATOR: char = '+';
+
+const ROOT: usize = 0;
+const MAGIC: &[u8; 8] = b"GADDAG01";
+const HEADER_SIZE: usize = 16;
+const RECORD_SIZE: usize = 8;
+const TERMINAL: u32 = 1 << 31;
+
+// A word made from the letters of a rack, with the positions in the word
+// where a blank is used. The commonness is filled in by the dictionary when
+// it has a frequency list.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Anagram {
+    pub word: String,
+    pub blanks: Vec<usize>,
+    pub commonness: Option<u8>
+}
+
+impl fmt::Display for Anagram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.word)?;
+
+        for i in &self.blanks {
+            write!(f, ", blank as {}", self.word.chars().nth(*i).unwrap())?;
+        }
+
+        if let Some(commonness) = self.commonness {
+            write!(f, ", commonness {}", commonness)?;
+        }
+
+        Ok(())
+    }
+}
+
+// The letters that can be put in front of a word or after it to form another
+// word, like the S that hooks onto CARE to make SCARE and CARES.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Hooks {
+    pub word: String,
+    pub front: Vec<char>,
+    pub back: Vec<char>
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        self.front.is_empty() && self.back.is_empty()
+    }
+
+    // The words the hooks make, the ones with a front hook first
+    pub fn words(&self) -> Vec<String> {
+        let front = self.front.iter().map(|c| format!("{}{}", c, self.word));
+        let back = self.back.iter().map(|c| format!("{}{}", self.word, c));
+
+        front.chain(back).collect()
+    }
+}
+
+impl fmt::Display for Hooks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let letters = |hooks: &[char]| -> String {
+            if hooks.is_empty() {
+                String::from("-")
+            } else {
+                hooks.iter().collect()
+            }
+        };
+
+        write!(f, "front hooks {}, back hooks {}", letters(&self.front), letters(&self.back))?;
+
+        if !self.is_empty() {
+            write!(f, ", makes {}", self.words().join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+struct Node {
+    edges: Vec<(char, usize)>,
+    terminal: bool
+}
+
+impl Node {
+    fn new() -> Node {
+        Node { edges: vec![], terminal: false }
+    }
+}
+
+// A GADDAG holds every word in all the forms `REV(PREFIX)+SUFFIX`, so a word
+// can be found starting from any of its letters: first walking left from that
+// letter, and after the separator walking right. The states are minimized,
+// which shares every common suffix between the forms.
+//
+// See: Steven A. Gordon, "A Faster Scrabble Move Generation Algorithm" (1994)
+//
+// It's stored as a flat, little-endian byte layout, which is read as-is from
+// the compiled dictionary file (so it could just as well be memory-mapped):
+//
+// - The magic bytes `GADDAG01`, the number of nodes and the number of edges
+// - Every node: the index of its first edge and its edge count, with the
+//   highest bit set when the node ends a form
+// - Every edge: its letter and the index of the node it points to
+pub struct Gaddag {
+    data: Vec<u8>,
+    node_count: usize
+}
+
+impl Gaddag {
+    pub fn from_words(words: &[String]) -> Gaddag {
+        let mut forms = vec![];
+
+        for word in words {
+            let letters: Vec<char> = word.chars().collect();
+
+            for i in 1..=letters.len() {
+                let mut form: String = letters[..i].iter().rev().collect();
+
+                if i < letters.len() {
+                    form.push(SEPARATOR);
+                    form.extend(&letters[i..]);
+                }
+
+                forms.push(form);
+            }
+        }
+
+        forms.sort();
+        forms.dedup();
+
+        let mut builder = Builder::new();
+        for form in &forms {
+            builder.insert(form);
+        }
+        builder.finish()
+    }
+
+    pub fn load(path: &String) -> Result<Gaddag, String> {
+        let data = fs::read(path).map_err(|e| e.to_string())?;
+        Gaddag::from_bytes(data)
+    }
+
+    pub fn write(&self, path: &String) {
+        fs::write(path, &self.data).unwrap();
+    }
+
+    fn from_bytes(data: Vec<u8>) -> Result<Gaddag, String> {
+        if data.len() < HEADER_SIZE || &data[..MAGIC.len()] != MAGIC {
+            return Err(String::from("unknown format"));
+        }
+
+        let node_count = read_u32(&data, 8) as usize;
+        let edge_count = read_u32(&data, 12) as usize;
+        let expected = HEADER_SIZE + (node_count + edge_count) * RECORD_SIZE;
+
+        if data.len() != expected {
+            return Err(format!("expected {} bytes, got {}", expected, data.len()));
+        }
+
+        if node_count == 0 {
+            return Err(String::from("there's no root node"));
+        }
+
+        // Every offset is checked once here, so the lookups don't have to
+        let gaddag = Gaddag { data, node_count };
+
+        for node in 0..node_count {
+            let (_, last) = gaddag.edges(node);
+            if last > edge_count {
+                return Err(format!("the edges of node {} run past the last edge", node));
+            }
+        }
+
+        for index in 0..edge_count {
+            let offset = HEADER_SIZE + (node_count + index) * RECORD_SIZE;
+            let label = read_u32(&gaddag.data, offset);
+            let target = read_u32(&gaddag.data, offset + 4) as usize;
+
+            if char::from_u32(label).is_none() {
+                return Err(format!("edge {} has an invalid letter", index));
+            }
+            if target >= node_count {
+                return Err(format!("edge {} points to node {}, past the last node", index, target));
+            }
+        }
+
+        Ok(gaddag)
+    }
+
+    pub fn root(&self) -> usize {
+        ROOT
+    }
+
+    // Returns the range of the edges of a node
+    fn edges(&self, node: usize) -> (usize, usize) {
+        let offset = HEADER_SIZE + node * RECORD_SIZE;
+        let first = read_u32(&self.data, offset) as usize;
+        let count = (read_u32(&self.data, offset + 4) & !TERMINAL) as usize;
+
+        (first, first + count)
+    }
+
+    fn edge(&self, index: usize) -> (char, usize) {
+        let offset = HEADER_SIZE + (self.node_count + index) * RECORD_SIZE;
+        let label = char::from_u32(read_u32(&self.data, offset)).unwrap();
+
+        (label, read_u32(&self.data, offset + 4) as usize)
+    }
+
+    pub fn next(&self, node: usize, c: char) -> Option<usize> {
+        let (mut low, mut high) = self.edges(node);
+
+        while low < high {
+            let middle = (low + high) / 2;
+            let (label, target) = self.edge(middle);
+
+            if label == c {
+                return Some(target);
+            } else if label < c {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+
+        None
+    }
+
+    pub fn is_terminal(&self, node: usize) -> bool {
+        let offset = HEADER_SIZE + node * RECORD_SIZE;
+        read_u32(&self.data, offset + 4) & TERMINAL != 0
+    }
+
+    // The letters that can follow the given node, without the separator
+    pub fn letters(&self, node: usize) -> Vec<char> {
+        let (first, last) = self.edges(node);
+
+        (first..last)
+            .map(|i| self.edge(i).0)
+            .filter(|c| *c != SEPARATOR)
+            .collect()
+    }
+
+    // Every word that can be made with some or all of the given letters,
+    // where a `?` is a blank that can be any letter. Every word is in there in
+    // reverse without a separator, so that's what's walked.
+    //
+    // A letter from the rack is always used before a blank, which never
+    // scores less. That way every word is found once, and the blanks are
+    // only tried as the letters that can follow in the dictionary.
+    pub fn sub_anagrams(&self, letters: &str) -> Vec<Anagram> {
+        let mut rack: Vec<char> = letters.chars().collect();
+        let mut reversed = vec![];
+        let mut anagrams = vec![];
+
+        self.find_anagrams(ROOT, &mut rack, &mut reversed, &mut anagrams);
+        anagrams
+    }
+
+    fn find_anagrams(&self,
+                     node: usize,
+                     rack: &mut Vec<char>,
+                     reversed: &mut Vec<(char, bool)>,
+                     anagrams: &mut Vec<Anagram>) {
+
+        if self.is_terminal(node) && !reversed.is_empty() {
+            let length = reversed.len();
+
+            anagrams.push(Anagram {
+                word: reversed.iter().rev().map(|(c, _)| c).collect(),
+                blanks: reversed
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, (_, blank))| *blank)
+                    .map(|(i, _)| length - 1 - i)
+                    .rev()
+                    .collect(),
+                commonness: None
+            });
+        }
+
+        for c in self.letters(node) {
+            let (index, blank) = match rack.iter().position(|r| *r == c) {
+                Some(index) => (index, false),
+                None => match rack.iter().position(|r| *r == '?') {
+                    Some(index) => (index, true),
+                    None => continue
+                }
+            };
+
+            let next = self.next(node, c).unwrap();
+            let taken = rack.swap_remove(index);
+            reversed.push((c, blank));
+
+            self.find_anagrams(next, rack, reversed, anagrams);
+
+            reversed.pop();
+            rack.push(taken);
+            let last = rack.len() - 1;
+            rack.swap(index, last);
+        }
+    }
+
+    // Every word that matches the pattern, with a length within the bounds.
+    // When there's a rack, the letters for the wildcards have to come from
+    // it, the letters in the pattern are already on the board.
+    pub fn matching(&self,
+                    tokens: &[Token],
+                    (min_length, max_length): (usize, usize),
+                    rack: Option<&str>) -> Vec<String> {
+
+        let mut walk = PatternWalk {
+            gaddag: self,
+            tokens,
+            min_length,
+            max_length,
+            found: vec![]
+        };
+        let mut rack: Option<Vec<char>> = rack.map(|rack| rack.chars().collect());
+
+        walk.walk(ROOT, 0, &mut rack, &mut vec![]);
+
+        // A `*` next to another wildcard can match the same word twice
+        let mut found = walk.found;
+        found.sort();
+        found.dedup();
+        found
+    }
+
+    // Walking the word in reverse from the root ends where the letters in
+    // front of it can follow, and after the separator the letters that can
+    // come after it.
+    pub fn hooks(&self, word: &str) -> Hooks {
+        let mut hooks = Hooks { word: word.to_string(), front: vec![], back: vec![] };
+        if word.is_empty() {
+            return hooks;
+        }
+
+        let mut node = ROOT;
+        for c in word.chars().rev() {
+            node = match self.next(node, c) {
+                Some(node) => node,
+                None => return hooks
+            };
+        }
+
+        let terminal = |node: usize, c: char| {
+            self.next(node, c).is_some_and(|next| self.is_terminal(next))
+        };
+
+        hooks.front = self.letters(node).into_iter().filter(|c| terminal(node, *c)).collect();
+
+        if let Some(node) = self.next(node, SEPARATOR) {
+            hooks.back = self.letters(node).into_iter().filter(|c| terminal(node, *c)).collect();
+        }
+
+        hooks
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        let mut chars = word.chars();
+        let first = match chars.next() {
+            Some(c) => c,
+            None => return false
+        };
+
+        let mut node = match self.next(ROOT, first) {
+            Some(node) => node,
+            None => return false
+        };
+
+        let rest = chars.as_str();
+        if rest.is_empty() {
+            return self.is_terminal(node);
+        }
+
+        node = match self.next(node, SEPARATOR) {
+            Some(node) => node,
+            None => return false
+        };
+
+        for c in rest.chars() {
+            node = match self.next(node, c) {
+                Some(node) => node,
+                None => return false
+            };
+        }
+
+        self.is_terminal(node)
+    }
+}
+
+// Walks the words from their first letter, which is the first letter, the
+// separator and then the rest of the word in the GADDAG.
+struct PatternWalk<'a> {
+    gaddag: &'a Gaddag,
+    tokens: &'a [Token],
+    min_length: usize,
+    max_length: usize,
+    found: Vec<String>
+}
+
+impl PatternWalk<'_> {
+    fn walk(&mut self,
+            node: usize,
+            token: usize,
+            rack: &mut Option<Vec<char>>,
+            word: &mut Vec<char>) {
+
+        if token == self.tokens.len() {
+            let length = word.len();
+            if length > 1 && length >= self.min_length && self.gaddag.is_terminal(node) {
+                self.found.push(word.iter().collect());
+            }
+            return;
+        }
+
+        match self.tokens[token] {
+            Token::Letter(c) => self.extend(node, c, token + 1, false, rack, word),
+            Token::Any => {
+                for c in self.gaddag.letters(node) {
+                    self.extend(node, c, token + 1, true, rack, word);
+                }
+            },
+            Token::Many => {
+                self.walk(node, token + 1, rack, word);
+
+                for c in self.gaddag.letters(node) {
+                    self.extend(node, c, token, true, rack, word);
+                }
+            }
+        }
+    }
+
+    fn extend(&mut self,
+              node: usize,
+              c: char,
+              token: usize,
+              wildcard: bool,
+              rack: &mut Option<Vec<char>>,
+              word: &mut Vec<char>) {
+
+        if word.len() >= self.max_length {
+            return;
+        }
+
+        let next = if word.is_empty() {
+            self.gaddag.next(node, c).and_then(|next| self.gaddag.next(next, SEPARATOR))
+        } else {
+            self.gaddag.next(node, c)
+        };
+        let next = match next {
+            Some(next) => next,
+            None => return
+        };
+
+        // A letter from the rack is used before a blank
+        let taken = match rack {
+            Some(letters) if wildcard => {
+                let index = match letters.iter().position(|r| *r == c) {
+                    Some(index) => index,
+                    None => match letters.iter().position(|r| *r == '?') {
+                        Some(index) => index,
+                        None => return
+                    }
+                };
+                Some((index, letters.remove(index)))
+            },
+            _ => None
+        };
+
+        word.push(c);
+        self.walk(next, token, rack, word);
+        word.pop();
+
+        if let (Some(letters), Some((index, letter))) = (rack.as_mut(), taken) {
+            letters.insert(index, letter);
+        }
+    }
+}
+
+// Builds a minimal automaton from sorted input, registering every state once
+// it can't change anymore and merging it with an equal state if there's one.
+//
+// See: Daciuk et al., "Incremental Construction of Minimal Acyclic Finite-State
+// Automata" (2000)
+struct Builder {
+    nodes: Vec<Node>,
+    // Registered states by the hash of their contents
+    register: HashMap<u64, Vec<usize>>,
+    unchecked: Vec<(usize, char, usize)>,
+    previous: Vec<char>
+}
+
+impl Builder {
+    fn new() -> Builder {
+        Builder {
+            nodes: vec![Node::new()],
+            register: HashMap::new(),
+            unchecked: vec![],
+            previous: vec![]
+        }
+    }
+
+    fn insert(&mut self, form: &str) {
+        let chars: Vec<char> = form.chars().collect();
+        let common = chars
+            .iter()
+            .zip(&self.previous)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        self.minimize(common);
+
+        let mut node = match self.unchecked.last() {
+            Some((_, _, child)) => *child,
+            None => ROOT
+        };
+
+        for c in &chars[common..] {
+            let child = self.nodes.len();
+            self.nodes.push(Node::new());
+            self.nodes[node].edges.push((*c, child));
+            self.unchecked.push((node, *c, child));
+            node = child;
+        }
+
+        self.nodes[node].terminal = true;
+        self.previous = chars;
+    }
+
+    fn minimize(&mut self, down_to: usize) {
+        while self.unchecked.len() > down_to {
+            let (parent, c, child) = self.unchecked.pop().unwrap();
+            let key = self.hash(child);
+            let existing = self.register
+                .get(&key)
+                .and_then(|candidates| {
+                    candidates
+                        .iter()
+                        .find(|candidate| self.equal(**candidate, child))
+                        .cloned()
+                });
+
+            match existing {
+                Some(existing) => {
+                    let edge = self.nodes[parent]
+                        .edges
+                        .iter_mut()
+                        .find(|(label, _)| *label == c)
+                        .unwrap();
+
+                    edge.1 = existing;
+
+                    // The child is always the last node that was added, so
+                    // it can be dropped again.
+                    if child == self.nodes.len() - 1 {
+                        self.nodes.pop();
+                    }
+                },
+                None => {
+                    self.register.entry(key).or_default().push(child);
+                }
+            }
+        }
+    }
+
+    fn hash(&self, node: usize) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.nodes[node].terminal.hash(&mut hasher);
+        self.nodes[node].edges.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn equal(&self, a: usize, b: usize) -> bool {
+        self.nodes[a].terminal == self.nodes[b].terminal &&
+            self.nodes[a].edges == self.nodes[b].edges
+    }
+
+    fn finish(mut self) -> Gaddag {
+        self.minimize(0);
+
+        let edge_count: usize = self.nodes.iter().map(|node| node.edges.len()).sum();
+        let mut data = Vec::with_capacity(
+            HEADER_SIZE + (self.nodes.len() + edge_count) * RECORD_SIZE
+        );
+
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&(self.nodes.len() as u32).to_le_bytes());
+        data.extend_from_slice(&(edge_count as u32).to_le_bytes());
+
+        let mut first = 0;
+        for node in &self.nodes {
+            let mut count = node.edges.len() as u32;
+            if node.terminal {
+                count |= TERMINAL;
+            }
+
+            data.extend_from_slice(&(first as u32).to_le_bytes());
+            data.extend_from_slice(&count.to_le_bytes());
+            first += node.edges.len();
+        }
+
+        for node in &self.nodes {
+            for (c, target) in &node.edges {
+                data.extend_from_slice(&(*c as u32).to_le_bytes());
+                data.extend_from_slice(&(*target as u32).to_le_bytes());
+            }
+        }
+
+        Gaddag { data, node_count: self.nodes.len() }
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dictionary_generator::{normalize, read_language};
+    use crate::test_dir::TestDir;
+
+    fn test_gaddag() -> Gaddag {
+        let language = read_language(&String::from("data/test"));
+        let words: Vec<String> = fs::read_to_string("data/test/wordlist.txt")
+            .unwrap()
+            .lines()
+            .filter_map(|word| normalize(word, &language).ok())
+            .collect();
+
+        Gaddag::from_words(&words)
+    }
+
+    #[test]
+    fn test_contains() {
+        let gaddag = test_gaddag();
+
+        assert!(gaddag.contains("RESET"));
+        assert!(gaddag.contains("ZE"));
+        assert!(gaddag.contains("MN"));
+        assert!(!gaddag.contains("RESE"));
+        assert!(!gaddag.contains("ESET"));
+        assert!(!gaddag.contains("Z"));
+        assert!(!gaddag.contains(""));
+
+        // Too long for the board
+        assert!(!gaddag.contains("AANSPRAKELIJKHEIDSWAARDEVASTSTELLINGSVERANDERINGEN"));
+    }
+
+    #[test]
+    fn test_walk_from_the_middle() {
+        let words = vec![String::from("CARE"), String::from("CARES")];
+        let gaddag = Gaddag::from_words(&words);
+
+        // From the R: walk left over A and C, then right over E
+        let mut node = gaddag.root();
+        for c in ['R', 'A', 'C', SEPARATOR, 'E'] {
+            node = gaddag.next(node, c).unwrap();
+        }
+
+        assert!(gaddag.is_terminal(node));
+        assert_eq!(gaddag.letters(node), vec!['S']);
+    }
+
+    #[test]
+    fn test_hooks() {
+        let words: Vec<String> = ["ARE", "CARE", "CARES", "SCARE", "SCARES", "SCARF"]
+            .iter()
+            .map(|word| word.to_string())
+            .collect();
+        let gaddag = Gaddag::from_words(&words);
+
+        let hooks = gaddag.hooks("CARE");
+        assert_eq!(hooks.front, vec!['S']);
+        assert_eq!(hooks.back, vec!['S']);
+        assert_eq!(hooks.words(), vec!["SCARE", "CARES"]);
+        assert_eq!(
+            hooks.to_string(),
+            "front hooks S, back hooks S, makes SCARE, CARES"
+        );
+
+        assert_eq!(gaddag.hooks("ARE").front, vec!['C']);
+        assert_eq!(gaddag.hooks("SCAR").back, vec!['E', 'F']);
+        assert!(gaddag.hooks("SCARES").is_empty());
+        assert!(gaddag.hooks("XYZ").is_empty());
+        assert_eq!(gaddag.hooks("SCARES").to_string(), "front hooks -, back hooks -");
+    }
+
+    #[test]
+    fn test_minimized() {
+        let words = vec![String::from("CARE"), String::from("BARE")];
+        let gaddag = Gaddag::from_words(&words);
+
+        // Both "ERAC" and "ERAB" end in a single shared final state
+        let terminals = (0..gaddag.node_count).filter(|node| gaddag.is_terminal(*node)).count();
+        assert_eq!(terminals, 1);
+        assert!(gaddag.contains("CARE"));
+        assert!(gaddag.contains("BARE"));
+        assert!(!gaddag.contains("BARC"));
+    }
+
+    #[test]
+    fn test_anagrams() {
+        let gaddag = test_gaddag();
+
+        let words = |letters: &str, length: usize| -> Vec<String> {
+            let mut words: Vec<String> = gaddag
+                .sub_anagrams(letters)
+                .into_iter()
+                .map(|anagram| anagram.word)
+                .filter(|word| word.chars().count() == length)
+                .collect();
+            words.sort();
+            words
+        };
+
+        assert_eq!(words("TEERS", 5), vec!["EERST", "ESTER", "RESET"]);
+        assert_eq!(words("TEERS", 3), vec!["EET"]);
+        assert_eq!(words("TEERS", 2), vec!["ER"]);
+        assert_eq!(words("T??RS", 5), vec!["EERST", "ESTER", "RESET", "STAAR", "STEUR"]);
+        assert_eq!(gaddag.sub_anagrams("XYZ"), vec![]);
+
+        // The letters from the rack are used first
+        let mut anagrams = gaddag.sub_anagrams("T?ERS");
+        anagrams.sort_by(|a, b| a.word.cmp(&b.word));
+        assert_eq!(
+            anagrams[0],
+            Anagram { word: String::from("EERST"), blanks: vec![0], commonness: None }
+        );
+        assert_eq!(anagrams[0].to_string(), "EERST, blank as E");
+        assert!(anagrams.iter().any(|a| a.word == "ER" && a.blanks.is_empty()));
+
+        let anagrams = gaddag.sub_anagrams("S??AT");
+        let staar = anagrams.iter().find(|a| a.word == "STAAR").unwrap();
+        assert_eq!(staar.blanks, vec![2, 4]);
+        assert_eq!(staar.to_string(), "STAAR, blank as A, blank as R");
+    }
+
+    #[test]
+    fn test_matching() {
+        let gaddag = Gaddag::from_words(&[
+            String::from("SING"),
+            String::from("STING"),
+            String::from("STRING"),
+            String::from("STRINGS"),
+            String::from("SPRING"),
+            String::from("KING"),
+            String::from("SIGN")
+        ]);
+        let tokens = |pattern: &str| -> Vec<Token> {
+            crate::pattern::parse(pattern, &['G', 'I', 'K', 'N', 'P', 'R', 'S', 'T']).unwrap()
+        };
+
+        assert_eq!(
+            gaddag.matching(&tokens("S*ING"), (2, 15), None),
+            vec!["SING", "SPRING", "STING", "STRING"]
+        );
+        assert_eq!(gaddag.matching(&tokens("S??NG"), (2, 15), None), vec!["STING"]);
+        assert_eq!(gaddag.matching(&tokens("?ING"), (2, 15), None), vec!["KING", "SING"]);
+        assert_eq!(gaddag.matching(&tokens("*"), (7, 15), None), vec!["STRINGS"]);
+        assert_eq!(
+            gaddag.matching(&tokens("S**"), (5, 6), None),
+            vec!["SPRING", "STING", "STRING"]
+        );
+        assert_eq!(gaddag.matching(&tokens("SING?"), (2, 15), None), Vec::<String>::new());
+
+        // The wildcards have to be filled with the rack, a blank can be any
+        // letter
+        assert_eq!(
+            gaddag.matching(&tokens("S*ING"), (2, 15), Some("TR")),
+            vec!["SING", "STING", "STRING"]
+        );
+        assert_eq!(
+            gaddag.matching(&tokens("S*ING"), (2, 15), Some("P?")),
+            vec!["SING", "SPRING", "STING"]
+        );
+        assert_eq!(gaddag.matching(&tokens("*ING"), (2, 15), Some("")), Vec::<String>::new());
+        assert_eq!(gaddag.matching(&tokens("*ING"), (2, 15), Some("K")), vec!["KING"]);
+    }
+
+    #[test]
+    fn test_write_and_load() {
+        let gaddag = test_gaddag();
+        let dir = TestDir::new("gaddag", "data/test");
+        let path = dir.file("dictionary.gaddag");
+
+        gaddag.write(&path);
+        let loaded = Gaddag::load(&path).unwrap();
+
+        assert_eq!(loaded.data, gaddag.data);
+        assert!(loaded.contains("RESET"));
+    }
+
+    #[test]
+    fn test_invalid_bytes() {
+        assert!(Gaddag::from_bytes(b"SQLITE".to_vec()).is_err());
+
+        let gaddag = Gaddag::from_words(&[String::from("ZE")]);
+        let mut data = gaddag.data.clone();
+        data.pop();
+        assert!(Gaddag::from_bytes(data).is_err());
+
+        // The root points past the last edge
+        let mut data = gaddag.data.clone();
+        data[HEADER_SIZE..HEADER_SIZE + 4].copy_from_slice(&100u32.to_le_bytes());
+        assert_eq!(
+            Gaddag::from_bytes(data).err(),
+            Some(String::from("the edges of node 0 run past the last edge"))
+        );
+
+        // The first edge points past the last node
+        let mut data = gaddag.data.clone();
+        let offset = HEADER_SIZE + gaddag.node_count * RECORD_SIZE + 4;
+        data[offset..offset + 4].copy_from_slice(&100u32.to_le_bytes());
+        assert_eq!(
+            Gaddag::from_bytes(data).err(),
+            Some(String::from("edge 0 points to node 100, past the last node"))
+        );
+
+        // The first edge isn't a letter
+        let mut data = gaddag.data.clone();
+        let offset = HEADER_SIZE + gaddag.node_count * RECORD_SIZE;
+        data[offset..offset + 4].copy_from_slice(&0xD800u32.to_le_bytes());
+        assert!(Gaddag::from_bytes(data).is_err());
+    }
+}
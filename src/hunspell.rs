@@ -0,0 +1,501 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+
+// Suffixes are applied twice at most, for the continuation flags of a suffix
+const SUFFIX_DEPTH: usize = 2;
+
+// The affixes of a Hunspell dictionary, read from its `.aff` file. Only what's
+// needed to expand the words of the `.dic` file into all their forms is used:
+//
+// - `SET`: the encoding of both files, UTF-8 or ISO8859-1
+// - `FLAG`: how the flags are written, a single character by default, `long`
+//   for two characters and `num` for comma separated numbers
+// - `AF`: aliases for sets of flags, numbered from 1
+// - `PFX` and `SFX`: the prefixes and suffixes with their conditions
+// - `NEEDAFFIX`, `ONLYINCOMPOUND` and `FORBIDDENWORD`: stems that aren't a
+//   word on their own
+//
+// Compounding isn't supported, so words that only exist as compounds are
+// left out.
+#[derive(Debug)]
+pub struct Affixes {
+    flag_type: FlagType,
+    aliases: Vec<Vec<String>>,
+    prefixes: HashMap<String, AffixClass>,
+    suffixes: HashMap<String, AffixClass>,
+    need_affix: Option<String>,
+    only_in_compound: Option<String>,
+    forbidden: Option<String>
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum FlagType {
+    Char,
+    Long,
+    Number
+}
+
+// All the rules of a single prefix or suffix flag
+#[derive(Debug)]
+struct AffixClass {
+    // Whether it can be combined with an affix on the other side
+    cross_product: bool,
+    rules: Vec<AffixRule>
+}
+
+#[derive(Debug)]
+struct AffixRule {
+    strip: String,
+    add: String,
+    continuation: Vec<String>,
+    condition: Vec<Condition>
+}
+
+// A single character of the condition of a rule
+#[derive(Debug)]
+enum Condition {
+    Any,
+    OneOf(Vec<char>),
+    NoneOf(Vec<char>)
+}
+
+impl Condition {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Condition::Any => true,
+            Condition::OneOf(chars) => chars.contains(&c),
+            Condition::NoneOf(chars) => !chars.contains(&c)
+        }
+    }
+}
+
+impl Affixes {
+    fn from_contents(contents: &str) -> Result<Affixes, HunspellError> {
+        let mut affixes = Affixes {
+            flag_type: FlagType::Char,
+            aliases: vec![],
+            prefixes: HashMap::new(),
+            suffixes: HashMap::new(),
+            need_affix: None,
+            only_in_compound: None,
+            forbidden: None
+        };
+
+        // Only the first AF line holds the number of aliases, with numeric
+        // flags the ones after it can be a number too
+        let mut alias_count_seen = false;
+
+        let mut lines = contents.lines().enumerate();
+        while let Some((i, line)) = lines.next() {
+            let line_number = i + 1;
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let invalid = || HunspellError::InvalidLine {
+                line: line_number,
+                content: line.trim().to_string()
+            };
+
+            match fields.as_slice() {
+                ["FLAG", flag_type, ..] => {
+                    affixes.flag_type = match *flag_type {
+                        "long" => FlagType::Long,
+                        "num" => FlagType::Number,
+                        "UTF-8" => FlagType::Char,
+                        _ => return Err(invalid())
+                    }
+                },
+                ["AF", count] if !alias_count_seen && count.parse::<usize>().is_ok() => {
+                    alias_count_seen = true
+                },
+                ["AF", flags, ..] => {
+                    alias_count_seen = true;
+                    affixes.aliases.push(affixes.split_flags(flags))
+                },
+                ["NEEDAFFIX", flag, ..] => affixes.need_affix = Some(flag.to_string()),
+                ["ONLYINCOMPOUND", flag, ..] => {
+                    affixes.only_in_compound = Some(flag.to_string())
+                },
+                ["FORBIDDENWORD", flag, ..] => affixes.forbidden = Some(flag.to_string()),
+                [kind @ ("PFX" | "SFX"), flag, cross_product, count, ..] => {
+                    let count = count.parse::<usize>().map_err(|_| invalid())?;
+                    let mut class = AffixClass {
+                        cross_product: *cross_product == "Y",
+                        rules: vec![]
+                    };
+
+                    for _ in 0..count {
+                        let (i, line) = lines.next().ok_or_else(invalid)?;
+                        class.rules.push(affixes.parse_rule(kind, flag, line, i + 1)?);
+                    }
+
+                    if *kind == "PFX" {
+                        affixes.prefixes.insert(flag.to_string(), class);
+                    } else {
+                        affixes.suffixes.insert(flag.to_string(), class);
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        Ok(affixes)
+    }
+
+    // A rule like `SFX A y ies [^aeiou]y`, where a `0` means nothing is
+    // stripped or added, and the added part can have continuation flags.
+    fn parse_rule(&self,
+                  kind: &str,
+                  flag: &str,
+                  line: &str,
+                  line_number: usize) -> Result<AffixRule, HunspellError> {
+
+        let invalid = || HunspellError::InvalidLine {
+            line: line_number,
+            content: line.trim().to_string()
+        };
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 4 || fields[0] != kind || fields[1] != flag {
+            return Err(invalid());
+        }
+
+        let (add, continuation) = match fields[3].split_once('/') {
+            Some((add, flags)) => (add, self.parse_flags(flags)),
+            None => (fields[3], vec![])
+        };
+        let empty = |field: &str| if field == "0" { String::new() } else { field.to_string() };
+
+        Ok(AffixRule {
+            strip: empty(fields[2]),
+            add: empty(add),
+            continuation,
+            condition: parse_condition(fields.get(4).unwrap_or(&".")).ok_or_else(invalid)?
+        })
+    }
+
+    fn parse_flags(&self, flags: &str) -> Vec<String> {
+        // A number on its own is an alias when there are any
+        if !self.aliases.is_empty() {
+            if let Ok(alias) = flags.parse::<usize>() {
+                return self.aliases.get(alias.wrapping_sub(1)).cloned().unwrap_or_default();
+            }
+        }
+
+        self.split_flags(flags)
+    }
+
+    fn split_flags(&self, flags: &str) -> Vec<String> {
+        match self.flag_type {
+            FlagType::Char => flags.chars().map(String::from).collect(),
+            FlagType::Long => {
+                let chars: Vec<char> = flags.chars().collect();
+                chars.chunks(2).map(|flag| flag.iter().collect()).collect()
+            },
+            FlagType::Number => flags.split(',').map(String::from).collect()
+        }
+    }
+
+    // All the forms of a word from the `.dic` file with its flags.
+    fn expand(&self, word: &str, flags: &[String]) -> Vec<String> {
+        let has = |flag: &Option<String>| flag.as_ref().is_some_and(|flag| flags.contains(flag));
+        if has(&self.forbidden) || has(&self.only_in_compound) {
+            return vec![];
+        }
+
+        let mut forms = vec![];
+        if !has(&self.need_affix) {
+            forms.push(word.to_string());
+        }
+
+        let suffixed = self.add_suffixes(word, flags, SUFFIX_DEPTH);
+        forms.extend(suffixed.iter().map(|(form, _)| form.clone()));
+
+        for flag in flags {
+            let class = match self.prefixes.get(flag) {
+                Some(class) => class,
+                None => continue
+            };
+
+            for rule in &class.rules {
+                if let Some(form) = rule.apply_prefix(word) {
+                    forms.push(form);
+                }
+
+                if !class.cross_product {
+                    continue
+                }
+
+                for (suffixed, cross_product) in &suffixed {
+                    if let Some(form) = rule.apply_prefix(suffixed).filter(|_| *cross_product) {
+                        forms.push(form);
+                    }
+                }
+            }
+        }
+
+        forms
+    }
+
+    // The suffixed forms of a word, with whether they can still get a prefix.
+    fn add_suffixes(&self, word: &str, flags: &[String], depth: usize) -> Vec<(String, bool)> {
+        let mut forms = vec![];
+        if depth == 0 {
+            return forms;
+        }
+
+        for flag in flags {
+            let class = match self.suffixes.get(flag) {
+                Some(class) => class,
+                None => continue
+            };
+
+            for rule in &class.rules {
+                let form = match rule.apply_suffix(word) {
+                    Some(form) => form,
+                    None => continue
+                };
+
+                let continued = self.add_suffixes(&form, &rule.continuation, depth - 1);
+                for (continued, cross_product) in continued {
+                    forms.push((continued, cross_product && class.cross_product));
+                }
+
+                if !self.need_affix.as_ref().is_some_and(|flag| rule.continuation.contains(flag)) {
+                    forms.push((form, class.cross_product));
+                }
+            }
+        }
+
+        forms
+    }
+}
+
+impl AffixRule {
+    fn apply_suffix(&self, word: &str) -> Option<String> {
+        let stem = word.strip_suffix(self.strip.as_str())?;
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() < self.condition.len() || stem.is_empty() {
+            return None;
+        }
+
+        let end = &chars[chars.len() - self.condition.len()..];
+        if !self.condition.iter().zip(end).all(|(condition, c)| condition.matches(*c)) {
+            return None;
+        }
+
+        Some(format!("{}{}", stem, self.add))
+    }
+
+    fn apply_prefix(&self, word: &str) -> Option<String> {
+        let stem = word.strip_prefix(self.strip.as_str())?;
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() < self.condition.len() || stem.is_empty() {
+            return None;
+        }
+
+        if !self.condition.iter().zip(&chars).all(|(condition, c)| condition.matches(*c)) {
+            return None;
+        }
+
+        Some(format!("{}{}", self.add, stem))
+    }
+}
+
+// A condition like `[^aeiou]y`, where a `.` on its own matches anything.
+fn parse_condition(condition: &str) -> Option<Vec<Condition>> {
+    if condition == "." {
+        return Some(vec![]);
+    }
+
+    let mut conditions = vec![];
+    let mut chars = condition.chars();
+
+    while let Some(c) = chars.next() {
+        conditions.push(match c {
+            '.' => Condition::Any,
+            '[' => {
+                let mut set: Vec<char> = vec![];
+                let mut negated = false;
+
+                loop {
+                    match chars.next()? {
+                        ']' => break,
+                        '^' if set.is_empty() && !negated => negated = true,
+                        c => set.push(c)
+                    }
+                }
+
+                if negated {
+                    Condition::NoneOf(set)
+                } else {
+                    Condition::OneOf(set)
+                }
+            },
+            c => Condition::OneOf(vec![c])
+        });
+    }
+
+    Some(conditions)
+}
+
+// Reads a file in the encoding of the `SET` line in the `.aff` file. Hunspell
+// dictionaries that aren't in UTF-8 are nearly always in ISO8859-1, where every
+// byte is the character with the same code.
+fn read_encoded(path: &String, encoding: &str) -> String {
+    let bytes = fs::read(path).unwrap();
+
+    match encoding {
+        "ISO8859-1" => bytes.iter().map(|b| *b as char).collect(),
+        _ => String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+fn encoding(path: &String) -> String {
+    let bytes = fs::read(path).unwrap();
+
+    String::from_utf8_lossy(&bytes)
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("SET "))
+        .map_or(String::from("UTF-8"), |encoding| encoding.trim().to_string())
+}
+
+// Expands the words of a `.dic` file with the affixes of the `.aff` file, every
+// form with the line number of the word it came from.
+pub fn expand(dic_path: &String, aff_path: &String) -> Result<Vec<(usize, String)>, HunspellError> {
+    let encoding = encoding(aff_path);
+    let affixes = Affixes::from_contents(&read_encoded(aff_path, &encoding))?;
+
+    Ok(expand_words(&affixes, &read_encoded(dic_path, &encoding)))
+}
+
+fn expand_words(affixes: &Affixes, dic: &str) -> Vec<(usize, String)> {
+    let mut forms = vec![];
+
+    // The first line is the number of words
+    for (i, line) in dic.lines().enumerate().skip(1) {
+        // Anything after a tab or a space is morphological information
+        let entry = match line.split(['\t', ' ']).next() {
+            Some(entry) if !entry.is_empty() => entry,
+            _ => continue
+        };
+
+        let (word, flags) = match entry.split_once('/') {
+            Some((word, flags)) => (word, affixes.parse_flags(flags)),
+            None => (entry, vec![])
+        };
+
+        for form in affixes.expand(word, &flags) {
+            forms.push((i + 1, form));
+        }
+    }
+
+    forms
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum HunspellError {
+    InvalidLine { line: usize, content: String }
+}
+
+impl fmt::Display for HunspellError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HunspellError::InvalidLine { line, content } => {
+                write!(f, "line {} of the affixes: '{}' isn't a valid rule", line, content)
+            }
+        }
+    }
+}
+
+impl Error for HunspellError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFFIXES: &str = "SET UTF-8
+TRY esianrtolcdugmphbyfvkwz
+
+NEEDAFFIX X
+
+PFX U Y 1
+PFX U 0 un .
+
+SFX S Y 3
+SFX S y ies [^aey]y
+SFX S 0 s [aey]y
+SFX S 0 s [^y]
+
+SFX D N 1
+SFX D 0 ed/S .
+
+SFX N Y 1
+SFX N 0 ness/X .
+";
+
+    fn forms(dic: &str) -> Vec<String> {
+        let affixes = Affixes::from_contents(AFFIXES).unwrap();
+        let mut forms: Vec<String> = expand_words(&affixes, dic)
+            .into_iter()
+            .map(|(_, form)| form)
+            .collect();
+
+        forms.sort();
+        forms
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(forms("2\nfly/S\nday/S\n"), vec!["day", "days", "flies", "fly"]);
+        assert_eq!(forms("1\ntie/SU\n"), vec!["tie", "ties", "untie", "unties"]);
+
+        // The continuation of `ed` is applied on top of it, and a form that
+        // needs another affix isn't a word on its own
+        assert_eq!(forms("1\nbless/D\n"), vec!["bless", "blessed", "blesseds"]);
+        assert_eq!(forms("1\nkind/N\n"), vec!["kind"]);
+        assert_eq!(forms("1\npart/X\n"), Vec::<String>::new());
+
+        // Morphological fields are skipped, and the line numbers are kept
+        let affixes = Affixes::from_contents(AFFIXES).unwrap();
+        assert_eq!(
+            expand_words(&affixes, "2\nrun po:verb\n\ncat/S\tpo:noun\n"),
+            vec![
+                (2, String::from("run")),
+                (4, String::from("cat")),
+                (4, String::from("cats"))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_flags() {
+        let affixes = Affixes::from_contents("FLAG long\nAF 1\nAF AaBb\n").unwrap();
+        assert_eq!(affixes.parse_flags("AaBb"), vec!["Aa", "Bb"]);
+        assert_eq!(affixes.parse_flags("1"), vec!["Aa", "Bb"]);
+
+        let affixes = Affixes::from_contents("FLAG num\n").unwrap();
+        assert_eq!(affixes.parse_flags("101,7"), vec!["101", "7"]);
+
+        // An alias of a single numeric flag looks like the number of aliases
+        let affixes = Affixes::from_contents("FLAG num\nAF 2\nAF 7\nAF 101,7\n").unwrap();
+        assert_eq!(affixes.parse_flags("1"), vec!["7"]);
+        assert_eq!(affixes.parse_flags("2"), vec!["101", "7"]);
+    }
+
+    #[test]
+    fn test_invalid_affixes() {
+        assert_eq!(
+            Affixes::from_contents("SFX S Y 2\nSFX S 0 s .\n").unwrap_err(),
+            HunspellError::InvalidLine { line: 1, content: String::from("SFX S Y 2") }
+        );
+        assert_eq!(
+            Affixes::from_contents("SFX S Y 1\nSFX T 0 s .\n").unwrap_err(),
+            HunspellError::InvalidLine { line: 2, content: String::from("SFX T 0 s .") }
+        );
+        assert_eq!(
+            Affixes::from_contents("FLAG hex\n").unwrap_err().to_string(),
+            "line 1 of the affixes: 'FLAG hex' isn't a valid rule"
+        );
+    }
+}
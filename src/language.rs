@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+
+pub const BLANK: char = '?';
+
+// Everything that makes up a language: its letters with their points and the
+// number of tiles of each in the bag, the blanks, how words from the wordlist
+// are brought in shape and the layout of the board it's played on.
+//
+// It's read from a `language.txt` manifest in the language folder, with one
+// `key,value` line per setting:
+//
+// - `letter,A,1,7`: the letter, its points and the number of tiles in the bag
+// - `blanks,2`: the number of blanks in the bag (default: `0`)
+// - `layout,default`: the board layout that's used (default: `default`)
+// - `strip,'`: a character that's removed from the words in the wordlist
+// - `replace,Ï,I`: a character that's replaced in the words in the wordlist
+//
+// Empty lines and lines starting with a `#` are skipped.
+#[derive(Debug)]
+pub struct Language {
+    letters: Vec<Letter>,
+    alphabet: Vec<char>,
+    blanks: u16,
+    layout: String,
+    strip: Vec<char>,
+    replacements: HashMap<char, char>
+}
+
+#[derive(Debug)]
+struct Letter {
+    letter: char,
+    points: u16,
+    count: u16
+}
+
+impl Language {
+    pub fn parse(path: &String) -> Result<Language, LanguageError> {
+        let contents = fs::read_to_string(path).unwrap();
+        Language::from_contents(&contents)
+    }
+
+    fn from_contents(contents: &str) -> Result<Language, LanguageError> {
+        let mut language = Language {
+            letters: vec![],
+            alphabet: vec![],
+            blanks: 0,
+            layout: String::from("default"),
+            strip: vec![],
+            replacements: HashMap::new()
+        };
+
+        for (i, line) in contents.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+
+            let fields: Vec<&str> = line.split(',').collect();
+            let invalid = || LanguageError::InvalidLine {
+                line: line_number,
+                content: line.to_string()
+            };
+
+            match (fields[0], fields.len()) {
+                ("letter", 4) => {
+                    let letter = single_char(fields[1]).ok_or_else(invalid)?;
+                    if letter == BLANK || letter.is_lowercase() || !letter.is_alphabetic() {
+                        return Err(LanguageError::InvalidLetter { line: line_number, letter });
+                    }
+                    if language.letters.iter().any(|l| l.letter == letter) {
+                        return Err(LanguageError::DuplicateLetter { line: line_number, letter });
+                    }
+
+                    let points = number(fields[2], line_number)?;
+                    let count = number(fields[3], line_number)?;
+                    if count == 0 {
+                        return Err(LanguageError::NoTiles { line: line_number, letter });
+                    }
+
+                    language.letters.push(Letter { letter, points, count });
+                },
+                ("blanks", 2) => language.blanks = number(fields[1], line_number)?,
+                ("layout", 2) if !fields[1].is_empty() => {
+                    language.layout = fields[1].to_string()
+                },
+                ("strip", 2) => {
+                    language.strip.push(single_char(fields[1]).ok_or_else(invalid)?)
+                },
+                ("replace", 3) => {
+                    let from = single_char(fields[1]).ok_or_else(invalid)?;
+                    let to = single_char(fields[2]).ok_or_else(invalid)?;
+                    language.replacements.insert(from, to);
+                },
+                ("letter" | "blanks" | "layout" | "strip" | "replace", _) => {
+                    return Err(invalid())
+                },
+                (key, _) => {
+                    return Err(LanguageError::UnknownKey {
+                        line: line_number,
+                        key: key.to_string()
+                    })
+                }
+            }
+        }
+
+        if language.letters.is_empty() {
+            return Err(LanguageError::NoLetters);
+        }
+
+        language.letters.sort_by_key(|l| l.letter);
+        language.alphabet = language.letters.iter().map(|l| l.letter).collect();
+        Ok(language)
+    }
+
+    // The alphabet of a language are all the letters that are in the bag,
+    // except for the blank.
+    pub fn alphabet(&self) -> &[char] {
+        &self.alphabet
+    }
+
+    pub fn points(&self, c: char) -> Option<u16> {
+        if c == BLANK {
+            return Some(0);
+        }
+
+        self.letters.iter().find(|l| l.letter == c).map(|l| l.points)
+    }
+
+    // The number of tiles of a letter in the bag at the start of the game.
+    pub fn count(&self, c: char) -> u16 {
+        if c == BLANK {
+            return self.blanks;
+        }
+
+        self.letters.iter().find(|l| l.letter == c).map_or(0, |l| l.count)
+    }
+
+    pub fn layout(&self) -> &str {
+        &self.layout
+    }
+
+    // A rack can't hold more of a letter than there are in the bag.
+    pub fn fits_in_bag(&self, letters: &str) -> bool {
+        let mut counts: HashMap<char, u16> = HashMap::new();
+        for c in letters.chars() {
+            *counts.entry(c).or_insert(0) += 1;
+        }
+
+        counts.iter().all(|(c, n)| *n <= self.count(*c))
+    }
+
+    // Describes everything that decides how the words of the wordlist end up
+    // in the dictionary, so it can be rebuilt when any of it changes.
+    pub fn normalization(&self) -> String {
+        let mut replacements: Vec<String> = self.replacements
+            .iter()
+            .map(|(from, to)| format!("{}>{}", from, to))
+            .collect();
+        replacements.sort();
+
+        format!(
+            "alphabet={};strip={};replace={}",
+            self.alphabet.iter().collect::<String>(),
+            self.strip.iter().collect::<String>(),
+            replacements.join(" ")
+        )
+    }
+
+    // Uppercases a word from the wordlist and applies the stripping and
+    // replacing, it's not checked against the alphabet.
+    pub fn normalize(&self, word: &str) -> String {
+        word
+            .to_uppercase()
+            .chars()
+            .filter(|c| !self.strip.contains(c))
+            .map(|c| *self.replacements.get(&c).unwrap_or(&c))
+            .collect()
+    }
+}
+
+fn single_char(field: &str) -> Option<char> {
+    let mut chars = field.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None
+    }
+}
+
+fn number(field: &str, line: usize) -> Result<u16, LanguageError> {
+    field.parse::<u16>().map_err(|_| LanguageError::InvalidNumber {
+        line,
+        value: field.to_string()
+    })
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum LanguageError {
+    InvalidLine { line: usize, content: String },
+    UnknownKey { line: usize, key: String },
+    InvalidNumber { line: usize, value: String },
+    InvalidLetter { line: usize, letter: char },
+    DuplicateLetter { line: usize, letter: char },
+    NoTiles { line: usize, letter: char },
+    NoLetters
+}
+
+impl fmt::Display for LanguageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LanguageError::InvalidLine { line, content } => {
+                write!(f, "line {}: '{}' isn't a valid setting", line, content)
+            },
+            LanguageError::UnknownKey { line, key } => {
+                write!(f, "line {}: unknown setting '{}'", line, key)
+            },
+            LanguageError::InvalidNumber { line, value } => {
+                write!(f, "line {}: '{}' isn't a valid number", line, value)
+            },
+            LanguageError::InvalidLetter { line, letter } => {
+                write!(f, "line {}: '{}' can't be used as a letter", line, letter)
+            },
+            LanguageError::DuplicateLetter { line, letter } => {
+                write!(f, "line {}: the letter '{}' is declared twice", line, letter)
+            },
+            LanguageError::NoTiles { line, letter } => {
+                write!(f, "line {}: the letter '{}' has no tiles in the bag", line, letter)
+            },
+            LanguageError::NoLetters => write!(f, "no letters are declared")
+        }
+    }
+}
+
+impl Error for LanguageError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let language = Language::parse(&String::from("data/test-no/language.txt")).unwrap();
+
+        let alphabet = language.alphabet();
+        assert_eq!(alphabet.len(), 26);
+        assert_eq!(alphabet[0], 'A');
+        assert_eq!(&alphabet[23..], &['Å', 'Æ', 'Ø']);
+
+        assert_eq!(language.points('Æ'), Some(6));
+        assert_eq!(language.points('?'), Some(0));
+        assert_eq!(language.points('Q'), None);
+        assert_eq!(language.count('A'), 7);
+        assert_eq!(language.count('?'), 2);
+        assert_eq!(language.count('Q'), 0);
+        assert_eq!(language.layout(), "default");
+    }
+
+    #[test]
+    fn test_normalize() {
+        let language = Language::from_contents(
+            "letter,A,1,1\nletter,I,1,1\nstrip,'\nreplace,Ï,I\n"
+        ).unwrap();
+
+        assert_eq!(language.normalize("naïve"), String::from("NAIVE"));
+        assert_eq!(language.normalize("a'dam"), String::from("ADAM"));
+    }
+
+    #[test]
+    fn test_normalization() {
+        let language = Language::from_contents(
+            "letter,I,1,1\nletter,A,1,1\nstrip,'\nreplace,Ï,I\nreplace,Ä,A\n"
+        ).unwrap();
+
+        assert_eq!(language.normalization(), "alphabet=AI;strip=';replace=Ä>A Ï>I");
+    }
+
+    #[test]
+    fn test_fits_in_bag() {
+        let language = Language::from_contents("letter,A,1,2\nblanks,1\n").unwrap();
+
+        assert!(language.fits_in_bag("AA?"));
+        assert!(!language.fits_in_bag("AAA"));
+        assert!(!language.fits_in_bag("A??"));
+        assert!(!language.fits_in_bag("B"));
+    }
+
+    #[test]
+    fn test_invalid_manifests() {
+        let cases = [
+            ("", LanguageError::NoLetters),
+            ("# only a comment\n", LanguageError::NoLetters),
+            (
+                "letter,A,1\n",
+                LanguageError::InvalidLine { line: 1, content: String::from("letter,A,1") }
+            ),
+            (
+                "letter,A,1,1\nscore,A\n",
+                LanguageError::UnknownKey { line: 2, key: String::from("score") }
+            ),
+            (
+                "letter,A,one,1\n",
+                LanguageError::InvalidNumber { line: 1, value: String::from("one") }
+            ),
+            (
+                "letter,A,1,1\nblanks,-1\n",
+                LanguageError::InvalidNumber { line: 2, value: String::from("-1") }
+            ),
+            ("letter,a,1,1\n", LanguageError::InvalidLetter { line: 1, letter: 'a' }),
+            ("letter,?,0,2\n", LanguageError::InvalidLetter { line: 1, letter: '?' }),
+            (
+                "letter,A,1,1\n\nletter,A,2,1\n",
+                LanguageError::DuplicateLetter { line: 3, letter: 'A' }
+            ),
+            ("letter,A,1,0\n", LanguageError::NoTiles { line: 1, letter: 'A' })
+        ];
+
+        for (contents, error) in cases {
+            assert_eq!(Language::from_contents(contents).unwrap_err(), error);
+        }
+    }
+
+    #[test]
+    fn test_error_message() {
+        let error = Language::from_contents("letter,A,1,1\nletter,A,2,1\n").unwrap_err();
+        assert_eq!(error.to_string(), "line 2: the letter 'A' is declared twice");
+    }
+}
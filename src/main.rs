@@ -1,41 +1,289 @@
 mod board;
 mod dictionary_generator;
+mod gaddag;
+mod hunspell;
+mod language;
+mod pattern;
+mod report;
+#[cfg(test)]
+mod test_dir;
 
 use std::env;
+use std::fs;
+use std::process;
 use std::path::Path;
-use board::Board;
+use board::{Board, Ranking, Rules};
+use dictionary_generator::Dictionary;
+use report::Report;
+
+// How many of the best words and plays are shown when there's no `--limit`
+const SUGGESTION_LIMIT: usize = 20;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    let language = &args[1];
-    let letters = &args[2];
-    let default = String::from("default");
-    let layout = args.get(3).unwrap_or(&default);
-    let base_path = format!("data/{}", language);
+    // Options are given as `--name=value` anywhere after the command, and
+    // `--rebuild` generates the dictionary from scratch, even when it's up to
+    // date with the wordlist
+    let (options, args): (Vec<String>, Vec<String>) = env::args()
+        .partition(|arg| arg.starts_with("--"));
+    let rebuild = options.iter().any(|option| option == "--rebuild");
 
-    if !Path::new(&base_path).is_dir() {
-        panic!("Folder doesn't exist for language '{}'", language);
+    match args.get(1).map(String::as_str) {
+        Some("pattern") => find_pattern(&args[2..], &options, rebuild),
+        Some("check") => check_words(&args[2..], &options, rebuild),
+        Some("hooks") => find_hooks(&args[2..], &options, rebuild),
+        Some("report") => show_report(&args[2..], &options, rebuild),
+        Some("add") => edit_words(&args[2..], true, &options, rebuild),
+        Some("remove") => edit_words(&args[2..], false, &options, rebuild),
+        _ => find_plays(&args[1..], &options, rebuild)
     }
+}
 
-    let letterpoints_path = format!("{}/letterpoints.txt", base_path);
-    ensure_file_exists(&letterpoints_path);
+// `cargo run <language> <letters> [layout] [--sort=commonness]
+// [--min-commonness=...] [--limit=...]`: the best words to put on the current
+// board.
+fn find_plays(args: &[String], options: &[String], rebuild: bool) {
+    let language = &args[0];
+    let letters = &args[1].to_uppercase();
+    let base_path = language_path(language);
 
+    // The language is read before the dictionary is generated, so a mistake
+    // in it shows up before the wordlist is processed
+    let language = dictionary_generator::read_language(&base_path);
+    if !language.fits_in_bag(letters) {
+        panic!("There aren't enough tiles in the bag for the letters '{}'", letters);
+    }
+
+    let layout = match args.get(2) {
+        Some(layout) => layout.to_string(),
+        None => language.layout().to_string()
+    };
     let layout_path = format!("layout.{}.board", &layout);
     ensure_file_exists(&layout_path);
 
     let current_board_path = String::from("current.board");
     ensure_file_exists(&current_board_path);
 
-    let dictionary = dictionary_generator::generate(base_path);
+    let rules_path = format!("{}/rules.txt", base_path);
+    let rules = if Path::new(&rules_path).is_file() {
+        Rules::parse(&rules_path)
+            .unwrap_or_else(|e| panic!("Invalid rules file '{}': {}", rules_path, e))
+    } else {
+        Rules::default()
+    };
+
+    let ranking = Ranking {
+        by_commonness: match option(options, "sort") {
+            None | Some("points") => false,
+            Some("commonness") => true,
+            Some(sort) => panic!("Can't sort by '{}', only by points or commonness", sort)
+        },
+        min_commonness: number_option(options, "min-commonness").unwrap_or(0).min(100) as u8
+    };
+
+    let dictionary = load_dictionary(base_path, options, rebuild);
     let board = Board::new(
         letters,
         &dictionary,
-        &letterpoints_path,
         &layout_path,
-        &current_board_path
-    );
+        &current_board_path,
+        &rules,
+        ranking
+    )
+    .unwrap_or_else(|e| {
+        panic!("Invalid board '{}' with the layout '{}': {}", current_board_path, layout_path, e)
+    });
+
+    let limit = number_option(options, "limit").unwrap_or(SUGGESTION_LIMIT);
+
+    let anagrams = board.anagrams();
+    let best_anagrams: Vec<String> = anagrams
+        .iter()
+        .take(limit)
+        .map(|anagram| anagram.to_string())
+        .collect();
+    println!("{:?}", best_anagrams);
+    print_more(anagrams.len(), limit);
+
+    let plays = board.optimal_plays();
+    for play in plays.iter().take(limit) {
+        println!("{}", play);
+    }
+    print_more(plays.len(), limit);
+}
+
+// Tells how many of the suggestions were left out by the limit
+fn print_more(count: usize, limit: usize) {
+    if count > limit {
+        println!("... and {} more, show them with --limit={}", count - limit, count);
+    }
+}
+
+// `cargo run pattern <language> <pattern> [--rack=...] [--min=...] [--max=...]`:
+// every word that fits a pattern like `?A??E` or `S*ING`.
+fn find_pattern(args: &[String], options: &[String], rebuild: bool) {
+    let language = args.first().expect("No language given");
+    let pattern = args.get(1).expect("No pattern given").to_uppercase();
+    let rack = option(options, "rack").map(|rack| rack.to_uppercase());
+    let min_length = number_option(options, "min").unwrap_or(2);
+    let max_length = number_option(options, "max").unwrap_or(15);
+
+    let dictionary = load_dictionary(language_path(language), options, rebuild);
+    let words = dictionary
+        .matching(&pattern, min_length, max_length, rack.as_deref())
+        .unwrap_or_else(|e| panic!("Invalid pattern '{}': {}", pattern, e));
+
+    for word in words {
+        println!("{}", word);
+    }
+}
+
+// `cargo run check <language> [words...] [--file=...]`: whether the words can
+// be played, it exits with an error when any of them can't.
+fn check_words(args: &[String], options: &[String], rebuild: bool) {
+    let language = args.first().expect("No language given");
+    let mut words: Vec<String> = args[1..].to_vec();
+
+    if let Some(file) = option(options, "file") {
+        let file = file.to_string();
+        ensure_file_exists(&file);
+
+        let contents = fs::read_to_string(&file).unwrap();
+        words.extend(
+            contents
+                .lines()
+                .map(|line| line.trim().to_string())
+                .filter(|line| !line.is_empty())
+        );
+    }
+
+    if words.is_empty() {
+        panic!("No words given to check");
+    }
+
+    let dictionary = load_dictionary(language_path(language), options, rebuild);
+    let mut all_valid = true;
+
+    for word in words {
+        let check = dictionary.check(&word);
+        all_valid &= check.is_valid();
+        println!("{}: {}", word, check);
+    }
+
+    if !all_valid {
+        process::exit(1);
+    }
+}
+
+// `cargo run hooks <language> [words...]`: the letters that can be put in
+// front of or after the words. Without words, it's every word on the current
+// board that can still be hooked.
+fn find_hooks(args: &[String], options: &[String], rebuild: bool) {
+    let language = args.first().expect("No language given");
+    let base_path = language_path(language);
+    let dictionary = load_dictionary(base_path, options, rebuild);
+
+    if args.len() > 1 {
+        for word in &args[1..] {
+            let hooks = dictionary.hooks(&dictionary.language().normalize(word));
+            println!("{}: {}", hooks.word, hooks);
+        }
+        return;
+    }
+
+    let layout_path = format!("layout.{}.board", dictionary.language().layout());
+    ensure_file_exists(&layout_path);
+
+    let current_board_path = String::from("current.board");
+    ensure_file_exists(&current_board_path);
+
+    let letters = String::new();
+    let board = Board::new(
+        &letters,
+        &dictionary,
+        &layout_path,
+        &current_board_path,
+        &Rules::default(),
+        Ranking::default()
+    )
+    .unwrap_or_else(|e| {
+        panic!("Invalid board '{}' with the layout '{}': {}", current_board_path, layout_path, e)
+    });
+
+    for hookable_word in board.hookable_words() {
+        println!("{}", hookable_word);
+    }
+}
+
+// `cargo run report <language>`: what went into the dictionary, it's generated
+// first when it isn't up to date.
+fn show_report(args: &[String], options: &[String], rebuild: bool) {
+    let language = args.first().expect("No language given");
+    let base_path = language_path(language);
+    load_dictionary(base_path.clone(), options, rebuild);
+
+    let db_file = dictionary_generator::database_file(&base_path, option(options, "dictionary"));
+    let report = Report::read(&db_file)
+        .unwrap_or_else(|e| panic!("Couldn't read the report from '{}': {}", db_file, e));
+
+    println!("{}", report);
+}
+
+// `cargo run add <language> <words...>` and `cargo run remove <language>
+// <words...>`: puts the words in the `extra.txt` or `blocked.txt` overlay, and
+// updates the dictionary with them.
+fn edit_words(args: &[String], add: bool, options: &[String], rebuild: bool) {
+    let language = args.first().expect("No language given");
+    let words = &args[1..];
+    if words.is_empty() {
+        panic!("No words given");
+    }
+
+    let base_path = language_path(language);
+    if add {
+        dictionary_generator::add_words(&base_path, words);
+    } else {
+        dictionary_generator::remove_words(&base_path, words);
+    }
+
+    let dictionary = load_dictionary(base_path, options, rebuild);
+    for word in words {
+        println!("{}: {}", word, dictionary.check(word));
+    }
+}
+
+fn language_path(language: &String) -> String {
+    let base_path = format!("data/{}", language);
+
+    if !Path::new(&base_path).is_dir() {
+        panic!("Folder doesn't exist for language '{}'", language);
+    }
+
+    base_path
+}
+
+// `--dictionary=<name>` picks one of the named dictionaries of a language,
+// instead of the default one.
+fn load_dictionary(base_path: String, options: &[String], rebuild: bool) -> Dictionary {
+    let name = option(options, "dictionary");
+
+    if rebuild {
+        dictionary_generator::remove_generated(&base_path, name);
+    }
+
+    dictionary_generator::generate(base_path, name)
+}
+
+fn option<'a>(options: &'a [String], name: &str) -> Option<&'a str> {
+    let prefix = format!("--{}=", name);
+    options.iter().find_map(|option| option.strip_prefix(&prefix))
+}
 
-    println!("{:?}", board.anagrams());
+fn number_option(options: &[String], name: &str) -> Option<usize> {
+    option(options, name).map(|value| {
+        value
+            .parse::<usize>()
+            .unwrap_or_else(|_| panic!("Invalid number for --{}: {}", name, value))
+    })
 }
 
 fn ensure_file_exists(file_path: &String) {
@@ -0,0 +1,84 @@
+use std::error::Error;
+use std::fmt;
+
+// A part of a pattern like `?A??E` or `S*ING`, as used to find the words that
+// fit in a slot on the board.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Token {
+    // A letter that's already there
+    Letter(char),
+    // `?`: any single letter
+    Any,
+    // `*`: any number of letters, including none
+    Many
+}
+
+pub fn parse(pattern: &str, alphabet: &[char]) -> Result<Vec<Token>, PatternError> {
+    if pattern.is_empty() {
+        return Err(PatternError::Empty);
+    }
+
+    let mut tokens: Vec<Token> = pattern
+        .chars()
+        .map(|c| match c {
+            '?' => Ok(Token::Any),
+            '*' => Ok(Token::Many),
+            c if alphabet.contains(&c) => Ok(Token::Letter(c)),
+            c => Err(PatternError::UnknownLetter(c))
+        })
+        .collect::<Result<_, _>>()?;
+
+    // `**` matches the same words as `*`, it would only be walked once more
+    // for every extra star
+    tokens.dedup_by(|a, b| *a == Token::Many && *b == Token::Many);
+    Ok(tokens)
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum PatternError {
+    Empty,
+    UnknownLetter(char)
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatternError::Empty => write!(f, "the pattern is empty"),
+            PatternError::UnknownLetter(c) => {
+                write!(f, "'{}' isn't a letter of the alphabet, or a `?` or `*`", c)
+            }
+        }
+    }
+}
+
+impl Error for PatternError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let alphabet = ['A', 'E', 'G', 'I', 'N', 'S'];
+
+        assert_eq!(
+            parse("S*ING", &alphabet),
+            Ok(vec![
+                Token::Letter('S'),
+                Token::Many,
+                Token::Letter('I'),
+                Token::Letter('N'),
+                Token::Letter('G')
+            ])
+        );
+        assert_eq!(parse("S**ING", &alphabet), parse("S*ING", &alphabet));
+        assert_eq!(parse("***", &alphabet), Ok(vec![Token::Many]));
+        assert_eq!(
+            parse("?A", &alphabet),
+            Ok(vec![Token::Any, Token::Letter('A')])
+        );
+        assert_eq!(parse("", &alphabet), Err(PatternError::Empty));
+        assert_eq!(parse("SAX", &alphabet), Err(PatternError::UnknownLetter('X')));
+        assert_eq!(parse("sa", &alphabet), Err(PatternError::UnknownLetter('s')));
+    }
+}
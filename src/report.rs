@@ -0,0 +1,234 @@
+use crate::dictionary_generator::playable_words;
+use rusqlite::Connection;
+use std::collections::BTreeMap;
+use std::fmt;
+
+// How many normalized words are shown as an example
+const EXAMPLES: usize = 5;
+
+// What went into a generated dictionary: where it came from, what the
+// playable words look like and what was left out of the wordlist.
+#[derive(Debug)]
+pub struct Report {
+    pub metadata: BTreeMap<String, String>,
+    pub wordlist_words: usize,
+    pub extra_words: usize,
+    pub blocked_words: usize,
+    pub playable_words: usize,
+    pub lengths: BTreeMap<usize, usize>,
+    pub letters: BTreeMap<char, usize>,
+    pub duplicates: usize,
+    // The number of rejected lines for every reason
+    pub rejected: BTreeMap<String, usize>,
+    // Every line of the wordlist that was skipped, with why
+    pub skipped: Vec<(usize, String, String)>,
+    // The lines that had characters stripped or replaced, with what they became
+    pub normalized: Vec<(String, String)>,
+    pub two_letter_words: Vec<String>,
+    pub three_letter_words: Vec<String>
+}
+
+impl Report {
+    pub fn read(db_path: &String) -> Result<Report, rusqlite::Error> {
+        let conn = Connection::open(db_path)?;
+        let count = |table: &str| -> Result<usize, rusqlite::Error> {
+            let query = format!("SELECT COUNT(*) FROM {}", table);
+            conn.query_row(&query, [], |row| row.get(0))
+        };
+
+        let mut report = Report {
+            metadata: BTreeMap::new(),
+            wordlist_words: count("words")?,
+            extra_words: count("extra_words")?,
+            blocked_words: count("blocked_words")?,
+            playable_words: 0,
+            lengths: BTreeMap::new(),
+            letters: BTreeMap::new(),
+            duplicates: 0,
+            rejected: BTreeMap::new(),
+            skipped: vec![],
+            normalized: vec![],
+            two_letter_words: vec![],
+            three_letter_words: vec![]
+        };
+
+        let mut select = conn.prepare("SELECT key, value FROM metadata")?;
+        let rows = select.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        report.metadata = rows.collect::<Result<_, _>>()?;
+
+        let mut words = playable_words(db_path)?;
+        words.sort();
+        report.playable_words = words.len();
+
+        for word in words {
+            let length = word.chars().count();
+            *report.lengths.entry(length).or_insert(0) += 1;
+
+            for c in word.chars() {
+                *report.letters.entry(c).or_insert(0) += 1;
+            }
+
+            match length {
+                2 => report.two_letter_words.push(word),
+                3 => report.three_letter_words.push(word),
+                _ => {}
+            }
+        }
+
+        let mut select = conn.prepare(
+            "SELECT line, original, outcome, detail FROM import_log ORDER BY line, rowid"
+        )?;
+        let rows = select.query_map([], |row| {
+            Ok((
+                row.get::<_, usize>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?
+            ))
+        })?;
+
+        for row in rows {
+            let (line, original, outcome, detail) = row?;
+
+            match outcome.as_str() {
+                "duplicate" => {
+                    report.duplicates += 1;
+                    report.skipped.push((line, original, format!("a duplicate of {}", detail)));
+                },
+                "rejected" => {
+                    *report.rejected.entry(detail.clone()).or_insert(0) += 1;
+                    report.skipped.push((line, original, detail));
+                },
+                "normalized" => report.normalized.push((original, detail)),
+                _ => {}
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let metadata = |key: &str| self.metadata.get(key).map_or("unknown", String::as_str);
+
+        // Databases from before the import was logged only get the metadata
+        // that was there already
+        writeln!(f, "Imported at: {} UTC", metadata("imported_at"))?;
+        writeln!(f, "Wordlist hash: {}", metadata("wordlist_hash"))?;
+        writeln!(f, "Build options: {}", metadata("build_options"))?;
+        writeln!(f, "Schema version: {}", metadata("schema_version"))?;
+        writeln!(f)?;
+
+        writeln!(
+            f,
+            "Playable words: {} ({} from the wordlist, {} extra, {} blocked)",
+            self.playable_words,
+            self.wordlist_words,
+            self.extra_words,
+            self.blocked_words
+        )?;
+
+        writeln!(f, "By length:")?;
+        for (length, count) in &self.lengths {
+            writeln!(f, "  {:>2}: {}", length, count)?;
+        }
+
+        let total: usize = self.letters.values().sum();
+        writeln!(f, "Letters:")?;
+        for (letter, count) in &self.letters {
+            let share = 100.0 * *count as f64 / total as f64;
+            writeln!(f, "  {}: {} ({:.1}%)", letter, count, share)?;
+        }
+        writeln!(f)?;
+
+        if !self.metadata.contains_key("imported_at") {
+            writeln!(f, "Skipped lines: unknown, generate it with --rebuild to see them")?;
+        } else {
+            let rejected: usize = self.rejected.values().sum();
+            writeln!(f, "Skipped lines: {}", self.duplicates + rejected)?;
+            writeln!(f, "  duplicates: {}", self.duplicates)?;
+            for (reason, count) in &self.rejected {
+                writeln!(f, "  {}: {}", reason, count)?;
+            }
+            for (line, original, reason) in &self.skipped {
+                writeln!(f, "  line {} '{}': {}", line, original, reason)?;
+            }
+
+            let examples: Vec<String> = self.normalized
+                .iter()
+                .take(EXAMPLES)
+                .map(|(original, word)| format!("{} > {}", original, word))
+                .collect();
+            write!(f, "Normalized words: {}", self.normalized.len())?;
+            if !examples.is_empty() {
+                write!(f, ", like {}", examples.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "Two-letter words: {}", self.two_letter_words.join(" "))?;
+        write!(f, "Three-letter words: {}", self.three_letter_words.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dictionary_generator::{database_file, generate};
+    use crate::test_dir::TestDir;
+    use std::fs;
+
+    #[test]
+    fn test_report() {
+        let dir = TestDir::new("report", "data/test");
+        let base_path = dir.path.clone();
+        fs::write(
+            dir.file("wordlist.txt"),
+            "er\nEr\nm'n\neet\nreset\nnaïve\ne\nzwijntjesjagersz\n"
+        ).unwrap();
+        fs::write(dir.file("extra.txt"), "ze\n").unwrap();
+        fs::write(dir.file("blocked.txt"), "reset\n").unwrap();
+
+        generate(base_path.clone(), None);
+        let report = Report::read(&database_file(&base_path, None)).unwrap();
+
+        assert_eq!(report.wordlist_words, 4);
+        assert_eq!(report.extra_words, 1);
+        assert_eq!(report.blocked_words, 1);
+        assert_eq!(report.playable_words, 4);
+        assert_eq!(report.lengths, BTreeMap::from([(2, 3), (3, 1)]));
+        assert_eq!(report.letters.get(&'E'), Some(&4));
+        assert_eq!(report.two_letter_words, vec!["ER", "MN", "ZE"]);
+        assert_eq!(report.three_letter_words, vec!["EET"]);
+
+        assert_eq!(report.duplicates, 1);
+        assert_eq!(
+            report.rejected,
+            BTreeMap::from([
+                (String::from("'Ï' isn't in the alphabet"), 1),
+                (String::from("it's longer than 15 letters"), 1),
+                (String::from("it's shorter than 2 letters"), 1)
+            ])
+        );
+        assert_eq!(
+            report.skipped,
+            vec![
+                (2, String::from("Er"), String::from("a duplicate of ER")),
+                (6, String::from("naïve"), String::from("'Ï' isn't in the alphabet")),
+                (7, String::from("e"), String::from("it's shorter than 2 letters")),
+                (8, String::from("zwijntjesjagersz"), String::from("it's longer than 15 letters"))
+            ]
+        );
+        assert_eq!(report.normalized, vec![(String::from("m'n"), String::from("MN"))]);
+        assert!(report.metadata.contains_key("imported_at"));
+
+        let text = report.to_string();
+        assert!(text.contains("Playable words: 4 (4 from the wordlist, 1 extra, 1 blocked)"));
+        assert!(text.contains("Skipped lines: 4"));
+        assert!(text.contains("  line 6 'naïve': 'Ï' isn't in the alphabet\n"));
+        assert!(text.contains("Normalized words: 1, like m'n > MN"));
+        assert!(text.ends_with("Three-letter words: EET"));
+    }
+}
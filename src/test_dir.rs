@@ -0,0 +1,38 @@
+use std::env;
+use std::fs;
+use std::process;
+
+// A language directory for a test that's removed again when it's dropped,
+// also when an assertion in the test fails
+pub struct TestDir {
+    pub path: String
+}
+
+impl TestDir {
+    // Creates an empty language with the manifest of one of the languages in
+    // `data`. The process id keeps apart the runs of the tests that happen at
+    // the same time.
+    pub fn new(name: &str, language_from: &str) -> TestDir {
+        let path = env::temp_dir()
+            .join(format!("wordfeud-{}-{}", name, process::id()))
+            .to_string_lossy()
+            .into_owned();
+
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        fs::copy(format!("{}/language.txt", language_from), format!("{}/language.txt", path))
+            .unwrap();
+
+        TestDir { path }
+    }
+
+    pub fn file(&self, name: &str) -> String {
+        format!("{}/{}", self.path, name)
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}